@@ -0,0 +1,250 @@
+/// BN128 Addition, Scalar Multiplication and Pairing operations
+///
+/// Adpted from the frontier precompile:
+/// https://github.com/paritytech/frontier/blob/master/frame/evm/precompile/bn128/src/lib.rs
+use bn::{pairing_batch, AffineG1, AffineG2, Fq, Fq2, Fr, Group, Gt, G1, G2};
+use sp_std::vec::Vec;
+
+use crate::InvalidArgument;
+
+/// Copy bytes from input to target.
+fn read_input(source: &[u8], target: &mut [u8], offset: usize) {
+	// Out of bounds, nothing to copy.
+	if source.len() <= offset {
+		return;
+	}
+
+	// Find len to copy up to target len, but not out of bounds.
+	let len = core::cmp::min(target.len(), source.len() - offset);
+	target[..len].copy_from_slice(&source[offset..][..len]);
+	target.reverse();
+}
+
+pub(crate) fn read_fr(input: &[u8], start_inx: usize) -> Result<Fr, InvalidArgument> {
+	let mut buf = [0u8; 32];
+	read_input(input, &mut buf, start_inx);
+
+	Ok(bn::Fr::from_slice(&buf)?)
+}
+
+pub(crate) fn read_point(input: &[u8], start_inx: usize) -> Result<G1, InvalidArgument> {
+	let mut px_buf = [0u8; 32];
+	let mut py_buf = [0u8; 32];
+	read_input(input, &mut px_buf, start_inx);
+	read_input(input, &mut py_buf, start_inx + 32);
+
+	let px = Fq::from_slice(&px_buf)?;
+	let py = Fq::from_slice(&py_buf)?;
+
+	if px == Fq::zero() && py == Fq::zero() {
+		Ok(G1::zero())
+	} else {
+		Ok(AffineG1::new(px, py)?.into())
+	}
+}
+
+/// Read a G2 point from its four coordinates, imaginary parts first (as in EIP-197).
+pub(crate) fn read_g2(input: &[u8], start_inx: usize) -> Result<G2, InvalidArgument> {
+	let read_fq = |off: usize| -> Result<Fq, InvalidArgument> {
+		let mut buf = [0u8; 32];
+		read_input(input, &mut buf, start_inx + off);
+		Ok(Fq::from_slice(&buf)?)
+	};
+
+	let b_a_y = read_fq(0)?;
+	let b_a_x = read_fq(32)?;
+	let b_b_y = read_fq(64)?;
+	let b_b_x = read_fq(96)?;
+
+	let b_a = Fq2::new(b_a_x, b_a_y);
+	let b_b = Fq2::new(b_b_x, b_b_y);
+	if b_a.is_zero() && b_b.is_zero() {
+		Ok(G2::zero())
+	} else {
+		Ok(G2::from(AffineG2::new(b_a, b_b)?))
+	}
+}
+
+fn write_point(output: &mut [u8; 64], point: AffineG1) {
+	let mut buf = [0; 32];
+	point.x().to_big_endian(&mut buf).expect("buffer size is 32; qed");
+	buf.reverse();
+	output[..32].copy_from_slice(&buf);
+
+	let mut buf = [0; 32];
+	point.y().to_big_endian(&mut buf).expect("buffer size is 32; qed");
+	buf.reverse();
+	output[32..].copy_from_slice(&buf);
+}
+
+pub(crate) fn add(input: &[u8]) -> Result<[u8; 64], InvalidArgument> {
+	let p1 = read_point(input, 0)?;
+	let p2 = read_point(input, 64)?;
+
+	let mut output = [0u8; 64];
+	if let Some(point) = AffineG1::from_jacobian(p1 + p2) {
+		// point not at infinity
+		write_point(&mut output, point);
+	}
+	Ok(output)
+}
+
+pub(crate) fn mul(input: &[u8]) -> Result<[u8; 64], InvalidArgument> {
+	let p = read_point(input, 0)?;
+	let fr = read_fr(input, 64)?;
+
+	let mut output = [0u8; 64];
+	if let Some(point) = AffineG1::from_jacobian(p * fr) {
+		// point not at infinity
+		write_point(&mut output, point)
+	}
+	Ok(output)
+}
+
+/// The input is a sequence of 192 byte (G1, G2) pairs, an empty input pairs to one like in
+/// EIP-197. The chain extension rejects input whose length is not a multiple of 192, so trailing
+/// bytes that do not form a whole pair only reach this function from the fuzz targets, which
+/// ignore them.
+pub(crate) fn pairing(input: &[u8]) -> Result<bool, InvalidArgument> {
+	let mut vals = Vec::new();
+	for pair in input.chunks_exact(192) {
+		let a = read_point(pair, 0)?;
+		let b = read_g2(pair, 64)?;
+		vals.push((a, b));
+	}
+
+	Ok(pairing_batch(&vals) == Gt::one())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// A big-endian hex number as a little-endian word.
+	fn word(be: &str) -> Vec<u8> {
+		let be = format!("{be:0>64}");
+		let mut word: Vec<u8> = (0..64)
+			.step_by(2)
+			.map(|i| u8::from_str_radix(&be[i..i + 2], 16).unwrap())
+			.collect();
+		word.reverse();
+		word
+	}
+
+	fn words(be: &[&str]) -> Vec<u8> {
+		be.iter().flat_map(|w| word(w)).collect()
+	}
+
+	const G1_X: &str = "1";
+	const G1_Y: &str = "2";
+	const G1_NEG_Y: &str = "30644e72e131a029b85045b68181585d97816a916871ca8d3c208c16d87cfd45";
+	const G1_DOUBLE_X: &str = "030644e72e131a029b85045b68181585d97816a916871ca8d3c208c16d87cfd3";
+	const G1_DOUBLE_Y: &str = "15ed738c0e0a7c92e7845f96b2ae9c0a68a6a449e3538fc7ff3ebf7a5a18a2c4";
+	const FIELD_MODULUS: &str = "30644e72e131a029b85045b68181585d97816a916871ca8d3c208c16d87cfd47";
+
+	/// The G2 generator of EIP-197: x imaginary, x real, y imaginary, y real.
+	const G2: [&str; 4] = [
+		"198e9393920d483a7260bfb731fb5d25f1aa493335a9e71297e485b7aef312c2",
+		"1800deef121f1e76426a00665e5c4479674322d4f75edadd46debd5cd992f6ed",
+		"090689d0585ff075ec9e99ad690c3395bc4b313370b38ef355acdadcd122975b",
+		"12c85ea5db8c6deb4aab71808dcb408fe3d1e7690c43d37b4ce6cc0166fa7daa",
+	];
+
+	#[test]
+	fn add_doubles_generator() {
+		let input = words(&[G1_X, G1_Y, G1_X, G1_Y]);
+		let output = add(&input).ok().unwrap();
+		assert_eq!(output.to_vec(), words(&[G1_DOUBLE_X, G1_DOUBLE_Y]));
+	}
+
+	#[test]
+	fn add_point_at_infinity() {
+		let input = words(&[G1_X, G1_Y, "0", "0"]);
+		assert_eq!(add(&input).ok().unwrap().to_vec(), words(&[G1_X, G1_Y]));
+
+		let input = words(&[G1_X, G1_Y, G1_X, G1_NEG_Y]);
+		assert_eq!(add(&input).ok().unwrap(), [0u8; 64]);
+	}
+
+	#[test]
+	fn add_rejects_invalid_points() {
+		let input = words(&[G1_X, "3", G1_X, G1_Y]);
+		assert!(matches!(add(&input), Err(InvalidArgument::NotOnCurve)));
+
+		let input = words(&[FIELD_MODULUS, G1_Y, G1_X, G1_Y]);
+		assert!(matches!(add(&input), Err(InvalidArgument::NotInField)));
+	}
+
+	#[test]
+	fn mul_matches_eip196_vectors() {
+		let input = words(&[G1_X, G1_Y, "2"]);
+		assert_eq!(mul(&input).ok().unwrap().to_vec(), words(&[G1_DOUBLE_X, G1_DOUBLE_Y]));
+
+		let input = words(&[
+			"2bd3e6d0f3b142924f5ca7b49ce5b9d54c4703d7ae5648e61d02268b1a0a9fb7",
+			"21611ce0a6af85915e2f1d70300909ce2e49dfad4a4619c8390cae66cefdb204",
+			"11138ce750fa15c2",
+		]);
+		let expected = words(&[
+			"070a8d6a982153cae4be29d434e8faef8a47b274a053f5a4ee2a6c9c13c31e5c",
+			"031b8ce914eba3a9ffb989f9cdd5b0f01943074bf4f0f315690ec3cec6981afc",
+		]);
+		assert_eq!(mul(&input).ok().unwrap().to_vec(), expected);
+
+		let input = words(&[G1_X, G1_Y, "0"]);
+		assert_eq!(mul(&input).ok().unwrap(), [0u8; 64]);
+	}
+
+	#[test]
+	fn pairing_matches_eip197_vectors() {
+		// e(G1, G2) · e(-G1, G2) == 1
+		let input =
+			[words(&[G1_X, G1_Y]), words(&G2), words(&[G1_X, G1_NEG_Y]), words(&G2)].concat();
+		assert!(pairing(&input).ok().unwrap());
+
+		// e(G1, G2) != 1
+		let input = [words(&[G1_X, G1_Y]), words(&G2)].concat();
+		assert!(!pairing(&input).ok().unwrap());
+
+		// Pairs of points at infinity do not change the result.
+		let input =
+			[words(&[G1_X, G1_Y]), words(&G2), words(&[G1_X, G1_NEG_Y]), words(&G2), vec![0; 192]]
+				.concat();
+		assert!(pairing(&input).ok().unwrap());
+
+		// Like EIP-197, no pairs at all pair to one.
+		assert!(pairing(&[]).ok().unwrap());
+	}
+
+	#[test]
+	fn pairing_rejects_invalid_g2() {
+		let mut g2 = G2;
+		g2[3] = "1";
+		let input = [words(&[G1_X, G1_Y]), words(&g2)].concat();
+		assert!(matches!(pairing(&input), Err(InvalidArgument::NotOnCurve)));
+	}
+
+	#[test]
+	fn no_input_panics() {
+		let mut seed = 0x9e37_79b9_7f4a_7c15u64;
+		let mut input = [0u8; 0x300];
+		for round in 0..64 {
+			for chunk in input.chunks_mut(8) {
+				seed ^= seed << 13;
+				seed ^= seed >> 7;
+				seed ^= seed << 17;
+				chunk.copy_from_slice(&seed.to_le_bytes());
+			}
+			// Clear the top bytes of every other round so that some words are field elements.
+			if round % 2 == 0 {
+				input.chunks_mut(32).for_each(|word| word[31] = 0);
+			}
+			let _ = add(&input[..128]);
+			let _ = mul(&input[..96]);
+			let _ = pairing(&input);
+		}
+		let _ = add(&[0xff; 128]);
+		let _ = mul(&[0xff; 96]);
+		let _ = pairing(&[0xff; 0x300]);
+	}
+}
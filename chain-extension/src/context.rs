@@ -0,0 +1,75 @@
+/// Information about the chain and the current block, for Solidity's block and transaction
+/// globals.
+use frame_support::traits::Get;
+use frame_system::pallet_prelude::BlockNumberFor;
+
+/// The hash of block `number`, like the EVM `BLOCKHASH` opcode but with the runtime's
+/// `BlockHashCount` as window instead of 256 blocks.
+///
+/// The current block, future blocks and blocks older than the window have a zero hash.
+pub(crate) fn block_hash<T: frame_system::Config>(number: BlockNumberFor<T>) -> T::Hash {
+	let current = frame_system::Pallet::<T>::block_number();
+	if number >= current || current - number > T::BlockHashCount::get() {
+		return Default::default()
+	}
+	frame_system::Pallet::<T>::block_hash(number)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use frame_support::{construct_runtime, derive_impl, traits::ConstU64};
+	use sp_runtime::{testing::H256, BuildStorage};
+
+	type Block = frame_system::mocking::MockBlock<Test>;
+
+	construct_runtime!(
+		pub enum Test {
+			System: frame_system,
+		}
+	);
+
+	#[derive_impl(frame_system::config_preludes::TestDefaultConfig as frame_system::DefaultConfig)]
+	impl frame_system::Config for Test {
+		type Block = Block;
+		type BlockHashCount = ConstU64<4>;
+	}
+
+	fn hash_of(number: u64) -> H256 {
+		H256::repeat_byte(number as u8 + 1)
+	}
+
+	/// Run `test` in block `number`, after importing all blocks before it.
+	fn in_block(number: u64, test: impl FnOnce()) {
+		let storage = frame_system::GenesisConfig::<Test>::default().build_storage().unwrap();
+		sp_io::TestExternalities::from(storage).execute_with(|| {
+			for n in 1..=number {
+				System::initialize(&n, &hash_of(n - 1), &Default::default());
+				if n < number {
+					System::finalize();
+				}
+			}
+			test()
+		});
+	}
+
+	#[test]
+	fn block_hash_within_window() {
+		in_block(10, || {
+			assert_eq!(block_hash::<Test>(9), hash_of(9));
+			assert_eq!(block_hash::<Test>(6), hash_of(6));
+		});
+		// The genesis block is only visible within the window, like any other block.
+		in_block(4, || assert_eq!(block_hash::<Test>(0), hash_of(0)));
+	}
+
+	#[test]
+	fn block_hash_outside_window_is_zero() {
+		in_block(10, || {
+			assert_eq!(block_hash::<Test>(5), H256::zero());
+			assert_eq!(block_hash::<Test>(0), H256::zero());
+			assert_eq!(block_hash::<Test>(10), H256::zero());
+			assert_eq!(block_hash::<Test>(11), H256::zero());
+		});
+	}
+}
@@ -0,0 +1,472 @@
+//! Typed cross-chain calls for contracts on a parachain (`ext-xcm`).
+//!
+//! Contracts pass plain arguments and the chain extension builds the XCM programs, so Solidity
+//! libraries don't have to SCALE encode XCM by hand. Programs are executed and sent with the
+//! contract as origin, like the `xcm_execute` and `xcm_send` host functions of `pallet-contracts`.
+
+use crate::ReturnCode;
+use codec::{Compact, Decode, Encode};
+use frame_support::weights::Weight;
+use frame_system::pallet_prelude::BlockNumberFor;
+use sp_runtime::{AccountId32, MultiAddress, RuntimeDebug};
+use sp_std::vec::Vec;
+
+/// A `pallet_contracts::Call::call` to dispatch on a sibling chain.
+///
+/// The sibling is expected to run `pallet-contracts` with 32 byte account ids and `MultiAddress`
+/// lookups, like the contracts parachain does.
+#[derive(Clone, Encode, Decode, PartialEq, Eq, RuntimeDebug)]
+pub struct ContractCall {
+	/// The index of `pallet-contracts` in the runtime of the sibling.
+	pub pallet_index: u8,
+	/// The contract to call.
+	pub dest: [u8; 32],
+	/// The value to transfer to the contract.
+	pub value: u128,
+	/// The gas limit of the call.
+	pub gas_limit: Weight,
+	/// The weight the sibling may use to dispatch the call, at least `gas_limit`.
+	pub weight: Weight,
+	/// The input data of the call.
+	pub data: Vec<u8>,
+}
+
+impl ContractCall {
+	/// The call, encoded for the runtime of the sibling.
+	pub fn encode_call(&self) -> Vec<u8> {
+		// `call` has call index 6 in `pallet-contracts`.
+		(
+			self.pallet_index,
+			6u8,
+			MultiAddress::<AccountId32, ()>::Id(self.dest.into()),
+			Compact(self.value),
+			self.gas_limit,
+			None::<Compact<u128>>,
+			&self.data,
+		)
+			.encode()
+	}
+}
+
+/// The state of a query registered with [`XcmHandler::new_query`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum QueryStatus {
+	/// No response has arrived yet.
+	Pending = 0,
+	/// The queried program was executed successfully.
+	Succeeded = 1,
+	/// The queried program failed, e.g. because the balance was too low.
+	Failed = 2,
+}
+
+/// Executes and sends the XCM programs of the `ext-xcm` functions.
+///
+/// `()` is for runtimes without XCM: every function fails with [`ReturnCode::NotAvailable`].
+pub trait XcmHandler<T: frame_system::Config> {
+	/// The worst case weight of [`Self::reserve_transfer`].
+	fn reserve_transfer_weight() -> Weight;
+
+	/// The worst case weight of [`Self::transact`] and [`Self::query_balance`].
+	fn send_weight() -> Weight;
+
+	/// The worst case weight of [`Self::new_query`], [`Self::new_notify_query`] and
+	/// [`Self::take_response`].
+	fn query_weight() -> Weight;
+
+	/// Reserve-transfer `amount` of the native token from `contract` to `beneficiary` on the
+	/// sibling `para_id`, and return the weight used.
+	fn reserve_transfer(
+		contract: &T::AccountId,
+		para_id: u32,
+		beneficiary: [u8; 32],
+		amount: u128,
+	) -> Result<Weight, ReturnCode>;
+
+	/// Dispatch `call` on the sibling `para_id`, with the account of `contract` on the sibling as
+	/// origin. The execution is paid with up to `fee` of the relay chain token held by that
+	/// account. Returns the hash of the sent message.
+	fn transact(
+		contract: &T::AccountId,
+		para_id: u32,
+		call: ContractCall,
+		fee: u128,
+	) -> Result<[u8; 32], ReturnCode>;
+
+	/// Register a query for the response of the sibling `para_id` to a message of `contract`,
+	/// which only `contract` can take with [`Self::take_response`].
+	fn new_query(
+		contract: &T::AccountId,
+		para_id: u32,
+		timeout: BlockNumberFor<T>,
+	) -> Result<u64, ReturnCode>;
+
+	/// Like [`Self::new_query`], but the outcome is passed to `selector` of `contract` when the
+	/// response arrives, with up to `gas_limit`. The price of `gas_limit` is held from the balance
+	/// of `contract` until then, or until the query is removed after `timeout`.
+	fn new_notify_query(
+		contract: &T::AccountId,
+		para_id: u32,
+		timeout: BlockNumberFor<T>,
+		selector: [u8; 4],
+		gas_limit: Weight,
+	) -> Result<u64, ReturnCode>;
+
+	/// Ask the sibling `para_id` whether the account of `contract` holds at least `amount` of the
+	/// relay chain token, in addition to the `fee` paid for the query. The answer is the response
+	/// to `query_id`. Returns the hash of the sent message.
+	///
+	/// XCM has no instruction to read a balance, so the sibling withdraws `amount`, reports
+	/// whether that succeeded and deposits it back.
+	fn query_balance(
+		contract: &T::AccountId,
+		para_id: u32,
+		query_id: u64,
+		amount: u128,
+		fee: u128,
+	) -> Result<[u8; 32], ReturnCode>;
+
+	/// Take the response to `query_id`, `None` if there is no such query or `contract` did not
+	/// register it with [`Self::new_query`].
+	fn take_response(contract: &T::AccountId, query_id: u64) -> Option<QueryStatus>;
+}
+
+impl<T: frame_system::Config> XcmHandler<T> for () {
+	fn reserve_transfer_weight() -> Weight {
+		Weight::zero()
+	}
+
+	fn send_weight() -> Weight {
+		Weight::zero()
+	}
+
+	fn query_weight() -> Weight {
+		Weight::zero()
+	}
+
+	fn reserve_transfer(
+		_contract: &T::AccountId,
+		_para_id: u32,
+		_beneficiary: [u8; 32],
+		_amount: u128,
+	) -> Result<Weight, ReturnCode> {
+		Err(ReturnCode::NotAvailable)
+	}
+
+	fn transact(
+		_contract: &T::AccountId,
+		_para_id: u32,
+		_call: ContractCall,
+		_fee: u128,
+	) -> Result<[u8; 32], ReturnCode> {
+		Err(ReturnCode::NotAvailable)
+	}
+
+	fn new_query(
+		_contract: &T::AccountId,
+		_para_id: u32,
+		_timeout: BlockNumberFor<T>,
+	) -> Result<u64, ReturnCode> {
+		Err(ReturnCode::NotAvailable)
+	}
+
+	fn new_notify_query(
+		_contract: &T::AccountId,
+		_para_id: u32,
+		_timeout: BlockNumberFor<T>,
+		_selector: [u8; 4],
+		_gas_limit: Weight,
+	) -> Result<u64, ReturnCode> {
+		Err(ReturnCode::NotAvailable)
+	}
+
+	fn query_balance(
+		_contract: &T::AccountId,
+		_para_id: u32,
+		_query_id: u64,
+		_amount: u128,
+		_fee: u128,
+	) -> Result<[u8; 32], ReturnCode> {
+		Err(ReturnCode::NotAvailable)
+	}
+
+	fn take_response(_contract: &T::AccountId, _query_id: u64) -> Option<QueryStatus> {
+		None
+	}
+}
+
+#[cfg(feature = "ext-xcm")]
+pub use self::pallet_xcm_handler::PalletXcm;
+
+#[cfg(feature = "ext-xcm")]
+mod pallet_xcm_handler {
+	use super::{ContractCall, QueryStatus, XcmHandler};
+	use crate::ReturnCode;
+	use core::marker::PhantomData;
+	use frame_support::{
+		traits::{EnsureOrigin, Get},
+		weights::Weight,
+	};
+	use frame_system::pallet_prelude::{BlockNumberFor, OriginFor};
+	use pallet_xcm::WeightInfo;
+	use sp_std::{boxed::Box, vec};
+	use xcm::{latest::prelude::*, VersionedLocation, VersionedXcm};
+	use xcm_builder::{ExecuteController, SendController};
+	use xcm_executor::traits::{QueryResponseStatus, WeightBounds};
+
+	type RuntimeCallOf<T> = <T as pallet_xcm::Config>::RuntimeCall;
+	type Callbacks<T> = pallet_contracts_xcm_callbacks::Pallet<T>;
+
+	/// The [`XcmHandler`] of runtimes with `pallet-xcm`, which deliver responses to contracts
+	/// with `pallet-contracts-xcm-callbacks`.
+	pub struct PalletXcm<T>(PhantomData<T>);
+
+	/// The outcome of a query, `None` if there is no such query.
+	pub(super) fn query_status<B>(status: QueryResponseStatus<B>) -> Option<QueryStatus> {
+		match status {
+			QueryResponseStatus::Ready { response: Response::ExecutionResult(None), .. } =>
+				Some(QueryStatus::Succeeded),
+			QueryResponseStatus::Ready { .. } => Some(QueryStatus::Failed),
+			QueryResponseStatus::Pending { .. } => Some(QueryStatus::Pending),
+			QueryResponseStatus::NotFound | QueryResponseStatus::UnexpectedVersion => None,
+		}
+	}
+
+	impl<T: pallet_contracts_xcm_callbacks::Config> PalletXcm<T> {
+		fn origin(contract: &T::AccountId) -> OriginFor<T> {
+			frame_system::RawOrigin::Signed(contract.clone()).into()
+		}
+
+		fn sibling(para_id: u32) -> Location {
+			Location::new(1, [Parachain(para_id)])
+		}
+
+		/// The location of `contract` on this chain.
+		fn location(contract: &T::AccountId) -> Result<Location, ReturnCode> {
+			<T as pallet_xcm::Config>::ExecuteXcmOrigin::ensure_origin(Self::origin(contract))
+				.map_err(|_| ReturnCode::XcmFailed)
+		}
+
+		/// `location` as seen from `dest`.
+		fn reanchored(location: Location, dest: &Location) -> Result<Location, ReturnCode> {
+			location
+				.reanchored(dest, &T::UniversalLocation::get())
+				.map_err(|_| ReturnCode::XcmFailed)
+		}
+
+		fn reserve_transfer_message(
+			dest: Location,
+			beneficiary: [u8; 32],
+			amount: u128,
+		) -> Result<Xcm<RuntimeCallOf<T>>, ReturnCode> {
+			let asset: Asset = (Here, amount).into();
+			let fees = asset
+				.clone()
+				.reanchored(&dest, &T::UniversalLocation::get())
+				.map_err(|_| ReturnCode::XcmFailed)?;
+			Ok(Xcm(vec![TransferReserveAsset {
+				assets: asset.into(),
+				dest,
+				xcm: Xcm(vec![
+					BuyExecution { fees, weight_limit: Unlimited },
+					DepositAsset {
+						assets: Wild(AllCounted(1)),
+						beneficiary: Location::new(
+							0,
+							[AccountId32 { network: None, id: beneficiary }],
+						),
+					},
+				]),
+			}]))
+		}
+
+		fn send(
+			contract: &T::AccountId,
+			dest: Location,
+			message: Xcm<()>,
+		) -> Result<[u8; 32], ReturnCode> {
+			<pallet_xcm::Pallet<T> as SendController<_>>::send(
+				Self::origin(contract),
+				Box::new(VersionedLocation::from(dest)),
+				Box::new(VersionedXcm::from(message)),
+			)
+			.map_err(|_| ReturnCode::XcmFailed)
+		}
+	}
+
+	impl<T: pallet_contracts_xcm_callbacks::Config> XcmHandler<T> for PalletXcm<T> {
+		fn reserve_transfer_weight() -> Weight {
+			let message = Self::reserve_transfer_message(Self::sibling(0), [0; 32], 1);
+			let weight = message
+				.ok()
+				.and_then(|mut message| T::Weigher::weight(&mut message).ok())
+				.unwrap_or(Weight::MAX);
+			<T as pallet_xcm::Config>::WeightInfo::execute().saturating_add(weight)
+		}
+
+		fn send_weight() -> Weight {
+			<T as pallet_xcm::Config>::WeightInfo::send()
+		}
+
+		fn query_weight() -> Weight {
+			Callbacks::<T>::new_query_weight().max(Callbacks::<T>::take_response_weight())
+		}
+
+		fn reserve_transfer(
+			contract: &T::AccountId,
+			para_id: u32,
+			beneficiary: [u8; 32],
+			amount: u128,
+		) -> Result<Weight, ReturnCode> {
+			let mut message =
+				Self::reserve_transfer_message(Self::sibling(para_id), beneficiary, amount)?;
+			let max_weight = T::Weigher::weight(&mut message).map_err(|_| ReturnCode::XcmFailed)?;
+			<pallet_xcm::Pallet<T> as ExecuteController<_, _>>::execute(
+				Self::origin(contract),
+				Box::new(VersionedXcm::from(message)),
+				max_weight,
+			)
+			.map(|used| used.saturating_add(<T as pallet_xcm::Config>::WeightInfo::execute()))
+			.map_err(|_| ReturnCode::XcmFailed)
+		}
+
+		fn transact(
+			contract: &T::AccountId,
+			para_id: u32,
+			call: ContractCall,
+			fee: u128,
+		) -> Result<[u8; 32], ReturnCode> {
+			let dest = Self::sibling(para_id);
+			let beneficiary = Self::reanchored(Self::location(contract)?, &dest)?;
+			let fees: Asset = (Parent, fee).into();
+			let message = Xcm(vec![
+				WithdrawAsset(fees.clone().into()),
+				BuyExecution { fees, weight_limit: Unlimited },
+				Transact {
+					origin_kind: OriginKind::SovereignAccount,
+					require_weight_at_most: call.weight,
+					call: call.encode_call().into(),
+				},
+				RefundSurplus,
+				DepositAsset { assets: Wild(AllCounted(1)), beneficiary },
+			]);
+			Self::send(contract, dest, message)
+		}
+
+		fn new_query(
+			contract: &T::AccountId,
+			para_id: u32,
+			timeout: BlockNumberFor<T>,
+		) -> Result<u64, ReturnCode> {
+			// The sibling reports the contract as querier.
+			let querier = Self::location(contract)?;
+			Ok(Callbacks::<T>::new_polled_query(
+				contract.clone(),
+				querier,
+				Self::sibling(para_id),
+				timeout,
+			))
+		}
+
+		fn new_notify_query(
+			contract: &T::AccountId,
+			para_id: u32,
+			timeout: BlockNumberFor<T>,
+			selector: [u8; 4],
+			gas_limit: Weight,
+		) -> Result<u64, ReturnCode> {
+			let querier = Self::location(contract)?;
+			Callbacks::<T>::new_query(
+				contract.clone(),
+				querier,
+				Self::sibling(para_id),
+				timeout,
+				selector,
+				gas_limit,
+			)
+			.map_err(|_| ReturnCode::XcmFailed)
+		}
+
+		fn query_balance(
+			contract: &T::AccountId,
+			para_id: u32,
+			query_id: u64,
+			amount: u128,
+			fee: u128,
+		) -> Result<[u8; 32], ReturnCode> {
+			let dest = Self::sibling(para_id);
+			let beneficiary = Self::reanchored(Self::location(contract)?, &dest)?;
+			let destination = Self::reanchored(Location::here(), &dest)?;
+			let fees: Asset = (Parent, fee).into();
+			let message = Xcm(vec![
+				WithdrawAsset(fees.clone().into()),
+				BuyExecution { fees, weight_limit: Unlimited },
+				// Runs whether or not the withdrawal below fails.
+				SetAppendix(Xcm(vec![
+					ReportError(QueryResponseInfo {
+						destination,
+						query_id,
+						max_weight: Callbacks::<T>::response_weight(query_id),
+					}),
+					RefundSurplus,
+					DepositAsset { assets: Wild(AllCounted(1)), beneficiary },
+				])),
+				WithdrawAsset((Parent, amount).into()),
+			]);
+			Self::send(contract, dest, message)
+		}
+
+		fn take_response(contract: &T::AccountId, query_id: u64) -> Option<QueryStatus> {
+			Callbacks::<T>::take_response(contract, query_id).and_then(query_status)
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn encode_call_matches_contracts_call() {
+		let call = ContractCall {
+			pallet_index: 40,
+			dest: [7; 32],
+			value: 5,
+			gas_limit: Weight::from_parts(1_000, 64),
+			weight: Weight::from_parts(2_000, 128),
+			data: vec![1, 2, 3],
+		};
+		// pallet index, call index, `MultiAddress::Id`
+		let mut expected = vec![40, 6, 0];
+		expected.extend([7; 32]);
+		// The value and the gas limit are compact encoded.
+		expected.extend([0x14, 0xa1, 0x0f, 0x01, 0x01]);
+		// No storage deposit limit, then the data with its compact length.
+		expected.extend([0, 0x0c, 1, 2, 3]);
+		assert_eq!(call.encode_call(), expected);
+	}
+
+	#[cfg(feature = "ext-xcm")]
+	#[test]
+	fn query_status_of_responses() {
+		use pallet_xcm_handler::query_status;
+		use xcm::latest::{Error, Response};
+		use xcm_executor::traits::QueryResponseStatus;
+
+		let ready = |response| QueryResponseStatus::<u64>::Ready { response, at: 1 };
+		assert_eq!(
+			query_status(ready(Response::ExecutionResult(None))),
+			Some(QueryStatus::Succeeded)
+		);
+		assert_eq!(
+			query_status(ready(Response::ExecutionResult(Some((3, Error::Barrier))))),
+			Some(QueryStatus::Failed)
+		);
+		// Anything but an execution result is not the answer to one of our queries.
+		assert_eq!(query_status(ready(Response::Null)), Some(QueryStatus::Failed));
+		assert_eq!(
+			query_status(QueryResponseStatus::<u64>::Pending { timeout: 10 }),
+			Some(QueryStatus::Pending)
+		);
+		assert_eq!(query_status(QueryResponseStatus::<u64>::NotFound), None);
+	}
+}
@@ -0,0 +1,913 @@
+//! # Contracts Chain Extension
+//!
+//! The chain extension of the contracts node runtimes. It is generic over the runtime, which
+//! plugs it in with a single type alias:
+//!
+//! ```ignore
+//! pub type RuntimeChainExtension = contracts_chain_extension::Extension<Runtime>;
+//! ```
+//!
+//! Runtimes with `pallet-xcm` pass [`PalletXcm`] as second parameter to make the `ext-xcm`
+//! functions available, see [`XcmHandler`].
+//!
+//! Functions are grouped by the cargo feature that compiles them in, see [`ExtensionGroup`].
+//! Calling a function whose group is not enabled fails with [`ReturnCode::UnknownFunction`], just
+//! like calling a `func_id` that does not exist at all.
+//!
+//! The `profiling` feature reports the gas used by each function to the profiler of
+//! `contracts-dev-api`. Runtimes for production chains leave it off.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(any(feature = "ext-bn128", feature = "ext-plonk"))]
+mod bn128;
+#[cfg(feature = "ext-context")]
+mod context;
+mod cross_chain;
+#[cfg(feature = "ext-mimc")]
+mod mimc;
+#[cfg(feature = "ext-plonk")]
+mod plonk;
+#[cfg(feature = "ext-psp22")]
+mod psp22;
+
+/// The crypto functions with the input and output encoding of the chain extension, for the fuzz
+/// targets in `fuzz/`.
+#[cfg(feature = "fuzzing")]
+pub mod fuzzing {
+	pub fn bn128_add(input: &[u8]) -> Option<[u8; 64]> {
+		crate::bn128::add(input).ok()
+	}
+
+	pub fn bn128_mul(input: &[u8]) -> Option<[u8; 64]> {
+		crate::bn128::mul(input).ok()
+	}
+
+	pub fn bn128_pairing(input: &[u8]) -> Option<bool> {
+		crate::bn128::pairing(input).ok()
+	}
+
+	pub fn mimc_feistel(x_l: [u8; 32], x_r: [u8; 32]) -> Option<([u8; 32], [u8; 32])> {
+		let mut input = [0u8; 64];
+		input[..32].copy_from_slice(&x_l);
+		input[32..].copy_from_slice(&x_r);
+		let output = crate::mimc::feistel(&input).ok()?;
+		Some((output[..32].try_into().unwrap(), output[32..].try_into().unwrap()))
+	}
+}
+
+#[cfg(any(feature = "ext-bn128", feature = "ext-plonk"))]
+use bn::{FieldError, GroupError};
+use codec::{Decode, Encode};
+#[cfg(any(feature = "ext-context", feature = "ext-psp22", feature = "ext-xcm"))]
+use codec::MaxEncodedLen;
+use core::marker::PhantomData;
+#[cfg(feature = "ext-random")]
+use frame_support::traits::Randomness;
+#[cfg(any(
+	feature = "ext-bn128",
+	feature = "ext-context",
+	feature = "ext-mimc",
+	feature = "ext-plonk",
+	feature = "ext-xcm"
+))]
+use frame_support::weights::Weight;
+use frame_support::DefaultNoBound;
+#[cfg(any(feature = "ext-context", feature = "ext-mapping", feature = "ext-psp22"))]
+use frame_support::traits::Get;
+#[cfg(any(feature = "ext-context", feature = "ext-xcm"))]
+use frame_system::pallet_prelude::BlockNumberFor;
+use pallet_contracts::chain_extension::{
+	BufInBufOutState, ChainExtension, Environment, Ext, InitState, RetVal,
+};
+#[cfg(feature = "ext-context")]
+use pallet_contracts_context::RelayChainContext;
+#[cfg(feature = "ext-psp22")]
+use psp22::{AssetIdOf, BalanceOf};
+use scale_info::{prelude::format, TypeInfo};
+#[cfg(feature = "ext-context")]
+use sp_runtime::{traits::UniqueSaturatedInto, FixedPointNumber};
+#[cfg(feature = "ext-psp22")]
+use sp_runtime::DispatchResult;
+use sp_runtime::{DispatchError, RuntimeDebug};
+use sp_std::vec::Vec;
+
+#[cfg(feature = "ext-xcm")]
+pub use cross_chain::PalletXcm;
+pub use cross_chain::{ContractCall, QueryStatus, XcmHandler};
+
+type Allowlist<T> = pallet_chain_extension_allowlist::Pallet<T>;
+#[cfg(feature = "ext-mapping")]
+type AccountMapping<T> = pallet_account_mapping::Pallet<T>;
+#[cfg(feature = "ext-context")]
+type ContractsContext<T> = pallet_contracts_context::Pallet<T>;
+#[cfg(feature = "ext-context")]
+type TransactionPayment<T> = pallet_transaction_payment::Pallet<T>;
+#[cfg(feature = "ext-context")]
+type RelayChain<T> = <T as pallet_contracts_context::Config>::RelayChain;
+
+/// A group of chain extension functions that is selected by a cargo feature of the runtime.
+#[derive(Clone, Copy, Encode, Decode, PartialEq, Eq, RuntimeDebug, TypeInfo)]
+pub enum ExtensionGroup {
+	/// `ext-random`: `func_id` 1101, the ink! `FetchRandom` example.
+	Random,
+	/// `ext-bn128`: `func_id` 6, 7 and 8, the alt_bn128 precompiles of EIP-196 and EIP-197.
+	Bn128,
+	/// `ext-mimc`: `func_id` 220, the circomlib MiMC sponge Feistel permutation.
+	Mimc,
+	/// `ext-plonk`: `func_id` 300, snarkjs PLONK proof verification.
+	Plonk,
+	/// `ext-context`: information about the chain, the current block and transaction, `func_id`
+	/// 0x32 (`ORIGIN`), 0x3a (`GASPRICE`), 0x40 (`BLOCKHASH`), 0x41 (`COINBASE`), 0x46
+	/// (`CHAINID`), 0x48 (`BASEFEE`) and 0x13a (the tip of the transaction). On a parachain also
+	/// the relay parent number (0x200), the relay parent storage root (0x201) and the `ParaId`
+	/// (0x202).
+	Context,
+	/// `ext-xcm`: typed cross-chain calls, `func_id` 0x500 to 0x505, see [`XcmHandler`].
+	Xcm,
+	/// `ext-psp22`: PSP22 access to the assets of `pallet-assets`, `func_id` 0x600 to 0x609.
+	Psp22,
+	/// `ext-mapping`: the Ethereum address of an account (0x700) and the account of an Ethereum
+	/// address (0x701), see `pallet-account-mapping`.
+	AccountMapping,
+}
+
+/// The function groups compiled into this crate.
+pub fn active_groups() -> Vec<ExtensionGroup> {
+	[
+		(cfg!(feature = "ext-random"), ExtensionGroup::Random),
+		(cfg!(feature = "ext-bn128"), ExtensionGroup::Bn128),
+		(cfg!(feature = "ext-mimc"), ExtensionGroup::Mimc),
+		(cfg!(feature = "ext-plonk"), ExtensionGroup::Plonk),
+		(cfg!(feature = "ext-context"), ExtensionGroup::Context),
+		(cfg!(feature = "ext-xcm"), ExtensionGroup::Xcm),
+		(cfg!(feature = "ext-psp22"), ExtensionGroup::Psp22),
+		(cfg!(feature = "ext-mapping"), ExtensionGroup::AccountMapping),
+	]
+	.into_iter()
+	.filter_map(|(active, group)| active.then_some(group))
+	.collect()
+}
+
+sp_api::decl_runtime_apis! {
+	/// Information about the chain extension of the runtime.
+	pub trait ChainExtensionApi {
+		/// The function groups compiled into the runtime.
+		fn active_groups() -> Vec<ExtensionGroup>;
+	}
+}
+
+/// Reasons why the arguments of a call were rejected.
+#[derive(Clone, Copy, Debug)]
+#[allow(clippy::enum_variant_names)]
+pub(crate) enum InvalidArgument {
+	NotInField = 1,
+	NotOnCurve = 2,
+	NotInSubgroup = 3,
+}
+
+#[cfg(any(feature = "ext-bn128", feature = "ext-plonk"))]
+impl From<FieldError> for InvalidArgument {
+	fn from(_: FieldError) -> Self {
+		Self::NotInField
+	}
+}
+
+#[cfg(any(feature = "ext-bn128", feature = "ext-plonk"))]
+impl From<GroupError> for InvalidArgument {
+	fn from(err: GroupError) -> Self {
+		match err {
+			GroupError::NotOnCurve => Self::NotOnCurve,
+			GroupError::NotInSubgroup => Self::NotInSubgroup,
+		}
+	}
+}
+
+/// The status code returned to the contract by every chain extension function.
+///
+/// Any code other than `Success` means that nothing was written to the output buffer. When the
+/// debug buffer is enabled (e.g. for dry runs), a line describing the failure is appended to it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReturnCode {
+	/// The call succeeded and the result was written to the output buffer.
+	Success = 0,
+	/// A field element or coordinate is not smaller than the field modulus.
+	NotInField = 1,
+	/// A point is not on the curve.
+	NotOnCurve = 2,
+	/// A point is on the curve but not in the prime order subgroup.
+	NotInSubgroup = 3,
+	/// The runtime does not provide a function with this `func_id`.
+	UnknownFunction = 4,
+	/// The input buffer does not have the length the function expects.
+	BadInputLength = 5,
+	/// The output buffer is too small to hold the result.
+	OutputBufferTooSmall = 6,
+	/// The function is restricted and neither the contract nor its code hash is allowlisted.
+	NotAllowed = 7,
+	/// The requested value is not known in this context, e.g. the block author on a chain
+	/// without one.
+	NotAvailable = 8,
+	/// Executing or sending an XCM program failed.
+	XcmFailed = 9,
+	/// `pallet-assets` rejected the call, e.g. because of an insufficient balance or allowance, or
+	/// because the contract lacks the permission.
+	AssetsFailed = 10,
+}
+
+impl From<InvalidArgument> for ReturnCode {
+	fn from(reason: InvalidArgument) -> Self {
+		match reason {
+			InvalidArgument::NotInField => Self::NotInField,
+			InvalidArgument::NotOnCurve => Self::NotOnCurve,
+			InvalidArgument::NotInSubgroup => Self::NotInSubgroup,
+		}
+	}
+}
+
+/// Append a diagnostic line to the contract debug buffer, if it is enabled.
+fn debug_message<E: Ext>(ext: &mut E, func_id: u16, message: &str) {
+	if ext.debug_buffer_enabled() {
+		ext.append_debug_buffer(&format!("chain extension func_id {func_id}: {message}\n"));
+	}
+}
+
+/// Report a successful call to the contract.
+#[cfg_attr(not(feature = "profiling"), allow(unused_variables))]
+fn succeed<E: Ext>(ext: &mut E) -> Result<RetVal, DispatchError> {
+	#[cfg(feature = "profiling")]
+	contracts_dev_api::chain_extension_returned(ext.gas_meter().gas_left());
+	Ok(RetVal::Converging(ReturnCode::Success as u32))
+}
+
+/// Report a failed call to the contract.
+fn fail<E: Ext>(ext: &mut E, func_id: u16, code: ReturnCode) -> Result<RetVal, DispatchError> {
+	debug_message(ext, func_id, &format!("failed with {code:?} ({})", code as u32));
+	#[cfg(feature = "profiling")]
+	contracts_dev_api::chain_extension_returned(ext.gas_meter().gas_left());
+	Ok(RetVal::Converging(code as u32))
+}
+
+/// Fail with `BadInputLength` unless the input buffer is exactly `len` bytes long.
+fn check_input_len<E: Ext>(
+	env: &mut Environment<E, BufInBufOutState>,
+	func_id: u16,
+	len: u32,
+) -> Option<Result<RetVal, DispatchError>> {
+	let in_len = env.in_len();
+	if in_len == len {
+		return None
+	}
+	debug_message(env.ext(), func_id, &format!("expected {len} input bytes, got {in_len}"));
+	Some(fail(env.ext(), func_id, ReturnCode::BadInputLength))
+}
+
+/// Write the result of a successful call to the output buffer.
+fn write_output<E: Ext>(
+	env: &mut Environment<E, BufInBufOutState>,
+	func_id: u16,
+	output: &[u8],
+) -> Result<RetVal, DispatchError> {
+	match env.write(output, false, None) {
+		Ok(()) => succeed(env.ext()),
+		Err(err) if err == pallet_contracts::Error::<E::T>::OutputBufferTooSmall.into() =>
+			fail(env.ext(), func_id, ReturnCode::OutputBufferTooSmall),
+		Err(err) => Err(err),
+	}
+}
+
+/// Report the outcome of a call dispatched to `pallet-assets`.
+#[cfg(feature = "ext-psp22")]
+fn assets_result<E: Ext>(
+	ext: &mut E,
+	func_id: u16,
+	result: DispatchResult,
+) -> Result<RetVal, DispatchError> {
+	match result {
+		Ok(()) => succeed(ext),
+		Err(err) => {
+			debug_message(ext, func_id, &format!("pallet-assets: {err:?}"));
+			fail(ext, func_id, ReturnCode::AssetsFailed)
+		},
+	}
+}
+
+/// The chain extension, to be used as `pallet_contracts::Config::ChainExtension` of runtime `T`.
+///
+/// The `ext-xcm` functions are handled by `X`.
+#[derive(DefaultNoBound)]
+pub struct Extension<T, X = ()>(PhantomData<(T, X)>);
+
+impl<T, X> ChainExtension<T> for Extension<T, X>
+where
+	X: XcmHandler<T>,
+	T: pallet_contracts::Config
+		+ pallet_chain_extension_allowlist::Config
+		+ pallet_contracts_context::Config
+		+ pallet_authorship::Config
+		+ pallet_assets::Config
+		+ pallet_account_mapping::Config,
+{
+	fn call<E: Ext<T = T>>(
+		&mut self,
+		mut env: Environment<E, InitState>,
+	) -> Result<RetVal, DispatchError> {
+		let func_id = env.func_id();
+		// Lets `ContractsDevApi::profile_call` attribute the gas charged below to `func_id`.
+		#[cfg(feature = "profiling")]
+		contracts_dev_api::chain_extension_entered(func_id, env.ext().gas_meter().gas_left());
+
+		env.charge_weight(Allowlist::<T>::check_weight())?;
+		let contract = env.ext().address().clone();
+		let code_hash = *env.ext().own_code_hash();
+		if !Allowlist::<T>::is_allowed(func_id, &contract, &code_hash) {
+			debug_message(env.ext(), func_id, "calling contract is not allowlisted");
+			return fail(env.ext(), func_id, ReturnCode::NotAllowed)
+		}
+
+		match func_id {
+			// ink! FetchRandom chain extension example
+			#[cfg(feature = "ext-random")]
+			1101 => {
+				let mut env = env.buf_in_buf_out();
+				if let Some(ret) = check_input_len(&mut env, func_id, 32) {
+					return ret
+				}
+				let arg: [u8; 32] = env.read_as()?;
+				let random_seed = <T as pallet_contracts::Config>::Randomness::random(&arg).0;
+				write_output(&mut env, func_id, &random_seed.encode())
+			},
+
+			// bn128 curve addition
+			#[cfg(feature = "ext-bn128")]
+			6 => {
+				env.charge_weight(Weight::from_parts(7_940_000, 0))?; // Roughly
+
+				let mut env = env.buf_in_buf_out();
+				if let Some(ret) = check_input_len(&mut env, func_id, 128) {
+					return ret
+				}
+				let arg: [u8; 128] = env.read_as()?;
+
+				match crate::bn128::add(&arg) {
+					Ok(result) => write_output(&mut env, func_id, &result),
+					Err(reason) => fail(env.ext(), func_id, reason.into()),
+				}
+			},
+
+			// bn128 curve scalar multiplication
+			#[cfg(feature = "ext-bn128")]
+			7 => {
+				env.charge_weight(Weight::from_parts(168_074_000, 0))?; // Roughly
+
+				let mut env = env.buf_in_buf_out();
+				if let Some(ret) = check_input_len(&mut env, func_id, 96) {
+					return ret
+				}
+				let arg: [u8; 96] = env.read_as()?;
+
+				match crate::bn128::mul(&arg) {
+					Ok(result) => write_output(&mut env, func_id, &result),
+					Err(reason) => fail(env.ext(), func_id, reason.into()),
+				}
+			},
+
+			// bn128 curve pairing
+			#[cfg(feature = "ext-bn128")]
+			8 => {
+				let mut env = env.buf_in_buf_out();
+				let len = env.in_len();
+				if len % 192 != 0 {
+					debug_message(
+						env.ext(),
+						func_id,
+						&format!("expected a multiple of 192 input bytes, got {len}"),
+					);
+					return fail(env.ext(), func_id, ReturnCode::BadInputLength)
+				}
+				let pairs = (len / 192) as u64;
+				let weight = Weight::from_parts(1_511_820_000 + 1_142_264_000 * pairs, 0);
+				env.charge_weight(weight)?; // Roughly
+
+				let arg = env.read(len)?;
+
+				match crate::bn128::pairing(&arg) {
+					Ok(result) => write_output(&mut env, func_id, &result.encode()),
+					Err(reason) => fail(env.ext(), func_id, reason.into()),
+				}
+			},
+
+			// PLONK proof verification (snarkjs verifying keys and proofs)
+			#[cfg(feature = "ext-plonk")]
+			300 => {
+				let mut env = env.buf_in_buf_out();
+				let fixed_len = crate::plonk::VK_LEN + crate::plonk::PROOF_LEN;
+				let len = env.in_len() as usize;
+				if len < fixed_len ||
+					(len - fixed_len) % 32 != 0 ||
+					(len - fixed_len) / 32 > crate::plonk::MAX_PUBLIC_INPUTS
+				{
+					debug_message(
+						env.ext(),
+						func_id,
+						&format!(
+							"expected {fixed_len} bytes plus at most {} public inputs of 32 bytes, got {len}",
+							crate::plonk::MAX_PUBLIC_INPUTS,
+						),
+					);
+					return fail(env.ext(), func_id, ReturnCode::BadInputLength)
+				}
+				let public_inputs = ((len - fixed_len) / 32) as u64;
+				env.charge_weight(Weight::from_parts(
+					6_300_000_000 + 12_000_000 * public_inputs,
+					0,
+				))?; // Roughly
+
+				let arg = env.read(len as u32)?;
+
+				match crate::plonk::verify(&arg) {
+					Ok(result) => write_output(&mut env, func_id, &result.encode()),
+					Err(reason) => fail(env.ext(), func_id, reason.into()),
+				}
+			},
+
+			// mimc sponge hasher
+			#[cfg(feature = "ext-mimc")]
+			220 => {
+				env.charge_weight(Weight::from_parts(28_890_000, 0))?; // Roughly
+
+				let mut env = env.buf_in_buf_out();
+				if let Some(ret) = check_input_len(&mut env, func_id, 64) {
+					return ret
+				}
+				let arg: [u8; 64] = env.read_as()?;
+
+				match crate::mimc::feistel(&arg) {
+					Ok(result) => write_output(&mut env, func_id, &result),
+					Err(reason) => fail(env.ext(), func_id, reason.into()),
+				}
+			},
+
+			// EVM ORIGIN: the signer of the outer extrinsic
+			#[cfg(feature = "ext-context")]
+			0x32 => {
+				env.charge_weight(T::DbWeight::get().reads(1))?;
+
+				let mut env = env.buf_in_buf_out();
+				// Dry runs are not dispatched as signed extrinsics and leave nothing recorded. The
+				// origin is still known if the contract was called by it directly.
+				let origin = ContractsContext::<T>::tx_origin().or_else(|| {
+					let ext = env.ext();
+					if ext.caller_is_origin() {
+						ext.caller().account_id().ok().cloned()
+					} else {
+						None
+					}
+				});
+
+				match origin {
+					Some(origin) => write_output(&mut env, func_id, &origin.encode()),
+					None => fail(env.ext(), func_id, ReturnCode::NotAvailable),
+				}
+			},
+
+			// EVM GASPRICE: the fee for one unit of `ref_time`, without the fee multiplier and tip
+			#[cfg(feature = "ext-context")]
+			0x3a => {
+				env.charge_weight(T::DbWeight::get().reads(1))?;
+
+				let mut env = env.buf_in_buf_out();
+				let fee: u128 = TransactionPayment::<T>::weight_to_fee(Weight::from_parts(1, 0))
+					.unique_saturated_into();
+				write_output(&mut env, func_id, &fee.encode())
+			},
+
+			// The tip paid for the current transaction, zero if it is not signed
+			#[cfg(feature = "ext-context")]
+			0x13a => {
+				env.charge_weight(T::DbWeight::get().reads(1))?;
+
+				let mut env = env.buf_in_buf_out();
+				write_output(&mut env, func_id, &ContractsContext::<T>::tx_tip().encode())
+			},
+
+			// EVM BLOCKHASH: one of the last `BlockHashCount` blocks, zero otherwise
+			#[cfg(feature = "ext-context")]
+			0x40 => {
+				env.charge_weight(T::DbWeight::get().reads(1))?;
+
+				let mut env = env.buf_in_buf_out();
+				let len = BlockNumberFor::<T>::max_encoded_len() as u32;
+				if let Some(ret) = check_input_len(&mut env, func_id, len) {
+					return ret
+				}
+				let number: BlockNumberFor<T> = env.read_as()?;
+
+				write_output(&mut env, func_id, &crate::context::block_hash::<T>(number).encode())
+			},
+
+			// EVM COINBASE: the author of the current block
+			#[cfg(feature = "ext-context")]
+			0x41 => {
+				env.charge_weight(T::DbWeight::get().reads(2))?;
+
+				let mut env = env.buf_in_buf_out();
+				match pallet_authorship::Pallet::<T>::author() {
+					Some(author) => write_output(&mut env, func_id, &author.encode()),
+					None => fail(env.ext(), func_id, ReturnCode::NotAvailable),
+				}
+			},
+
+			// EVM CHAINID: a stable identifier of the chain
+			#[cfg(feature = "ext-context")]
+			0x46 => {
+				env.charge_weight(T::DbWeight::get().reads(1))?;
+
+				let mut env = env.buf_in_buf_out();
+				write_output(&mut env, func_id, &ContractsContext::<T>::chain_id())
+			},
+
+			// EVM BASEFEE: the fee multiplier of `pallet-transaction-payment`, as fixed point
+			// number with 18 decimals
+			#[cfg(feature = "ext-context")]
+			0x48 => {
+				env.charge_weight(T::DbWeight::get().reads(1))?;
+
+				let mut env = env.buf_in_buf_out();
+				let multiplier = pallet_transaction_payment::NextFeeMultiplier::<T>::get();
+				write_output(&mut env, func_id, &multiplier.into_inner().encode())
+			},
+
+			// The number of the relay chain block this block is built on
+			#[cfg(feature = "ext-context")]
+			0x200 => {
+				env.charge_weight(T::DbWeight::get().reads(1))?;
+
+				let mut env = env.buf_in_buf_out();
+				match RelayChain::<T>::relay_parent_number() {
+					Some(number) => write_output(&mut env, func_id, &number.encode()),
+					None => fail(env.ext(), func_id, ReturnCode::NotAvailable),
+				}
+			},
+
+			// The storage root of the relay chain block this block is built on
+			#[cfg(feature = "ext-context")]
+			0x201 => {
+				env.charge_weight(T::DbWeight::get().reads(1))?;
+
+				let mut env = env.buf_in_buf_out();
+				match RelayChain::<T>::relay_parent_storage_root() {
+					Some(root) => write_output(&mut env, func_id, &root),
+					None => fail(env.ext(), func_id, ReturnCode::NotAvailable),
+				}
+			},
+
+			// The `ParaId` of this chain
+			#[cfg(feature = "ext-context")]
+			0x202 => {
+				env.charge_weight(T::DbWeight::get().reads(1))?;
+
+				let mut env = env.buf_in_buf_out();
+				match RelayChain::<T>::para_id() {
+					Some(para_id) => write_output(&mut env, func_id, &para_id.encode()),
+					None => fail(env.ext(), func_id, ReturnCode::NotAvailable),
+				}
+			},
+
+			// Reserve-transfer native tokens to an account on a sibling chain
+			#[cfg(feature = "ext-xcm")]
+			0x500 => {
+				let charged = env.charge_weight(X::reserve_transfer_weight())?;
+
+				let mut env = env.buf_in_buf_out();
+				if let Some(ret) = check_input_len(&mut env, func_id, 52) {
+					return ret
+				}
+				let (para_id, beneficiary, amount): (u32, [u8; 32], u128) = env.read_as()?;
+
+				match X::reserve_transfer(&contract, para_id, beneficiary, amount) {
+					Ok(weight) => {
+						env.adjust_weight(charged, weight);
+						succeed(env.ext())
+					},
+					Err(code) => fail(env.ext(), func_id, code),
+				}
+			},
+
+			// Call a contract on a sibling chain
+			#[cfg(feature = "ext-xcm")]
+			0x501 => {
+				let mut env = env.buf_in_buf_out();
+				let len = env.in_len();
+				// The call data is decoded and copied into the message.
+				let per_byte = Weight::from_parts(2_000 * len as u64, 0); // Roughly
+				env.charge_weight(X::send_weight().saturating_add(per_byte))?;
+
+				let (para_id, call, fee): (u32, ContractCall, u128) = env.read_as_unbounded(len)?;
+
+				match X::transact(&contract, para_id, call, fee) {
+					Ok(hash) => write_output(&mut env, func_id, &hash),
+					Err(code) => fail(env.ext(), func_id, code),
+				}
+			},
+
+			// Ask a sibling chain whether the contract holds an amount of the relay chain token
+			#[cfg(feature = "ext-xcm")]
+			0x502 => {
+				env.charge_weight(X::send_weight())?;
+
+				let mut env = env.buf_in_buf_out();
+				if let Some(ret) = check_input_len(&mut env, func_id, 44) {
+					return ret
+				}
+				let (para_id, query_id, amount, fee): (u32, u64, u128, u128) = env.read_as()?;
+
+				match X::query_balance(&contract, para_id, query_id, amount, fee) {
+					Ok(hash) => write_output(&mut env, func_id, &hash),
+					Err(code) => fail(env.ext(), func_id, code),
+				}
+			},
+
+			// Register a query for the response of a sibling chain
+			#[cfg(feature = "ext-xcm")]
+			0x503 => {
+				env.charge_weight(X::query_weight())?;
+
+				let mut env = env.buf_in_buf_out();
+				let len = 4 + BlockNumberFor::<T>::max_encoded_len() as u32;
+				if let Some(ret) = check_input_len(&mut env, func_id, len) {
+					return ret
+				}
+				let (para_id, timeout): (u32, BlockNumberFor<T>) = env.read_as()?;
+
+				match X::new_query(&contract, para_id, timeout) {
+					Ok(query_id) => write_output(&mut env, func_id, &query_id.encode()),
+					Err(code) => fail(env.ext(), func_id, code),
+				}
+			},
+
+			// Take the response to a query: 0 pending, 1 succeeded, 2 failed
+			#[cfg(feature = "ext-xcm")]
+			0x504 => {
+				env.charge_weight(X::query_weight())?;
+
+				let mut env = env.buf_in_buf_out();
+				if let Some(ret) = check_input_len(&mut env, func_id, 8) {
+					return ret
+				}
+				let query_id: u64 = env.read_as()?;
+
+				match X::take_response(&contract, query_id) {
+					Some(status) => write_output(&mut env, func_id, &[status as u8]),
+					None => fail(env.ext(), func_id, ReturnCode::NotAvailable),
+				}
+			},
+
+			// Register a query whose response is passed to a selector of the contract
+			#[cfg(feature = "ext-xcm")]
+			0x505 => {
+				env.charge_weight(X::query_weight())?;
+
+				let mut env = env.buf_in_buf_out();
+				// The gas limit is passed as `ref_time` and `proof_size`, each a `u64`.
+				let len = 4 + BlockNumberFor::<T>::max_encoded_len() as u32 + 4 + 16;
+				if let Some(ret) = check_input_len(&mut env, func_id, len) {
+					return ret
+				}
+				let (para_id, timeout, selector, ref_time, proof_size): (
+					u32,
+					BlockNumberFor<T>,
+					[u8; 4],
+					u64,
+					u64,
+				) = env.read_as()?;
+				let gas_limit = Weight::from_parts(ref_time, proof_size);
+
+				match X::new_notify_query(&contract, para_id, timeout, selector, gas_limit) {
+					Ok(query_id) => write_output(&mut env, func_id, &query_id.encode()),
+					Err(code) => fail(env.ext(), func_id, code),
+				}
+			},
+
+			// PSP22 total_supply
+			#[cfg(feature = "ext-psp22")]
+			0x600 => {
+				env.charge_weight(T::DbWeight::get().reads(1))?;
+
+				let mut env = env.buf_in_buf_out();
+				let len = AssetIdOf::<T>::max_encoded_len() as u32;
+				if let Some(ret) = check_input_len(&mut env, func_id, len) {
+					return ret
+				}
+				let id: AssetIdOf<T> = env.read_as()?;
+
+				write_output(&mut env, func_id, &crate::psp22::total_supply::<T>(id).encode())
+			},
+
+			// PSP22 balance_of
+			#[cfg(feature = "ext-psp22")]
+			0x601 => {
+				env.charge_weight(T::DbWeight::get().reads(1))?;
+
+				let mut env = env.buf_in_buf_out();
+				let len = <(AssetIdOf<T>, T::AccountId)>::max_encoded_len() as u32;
+				if let Some(ret) = check_input_len(&mut env, func_id, len) {
+					return ret
+				}
+				let (id, owner): (AssetIdOf<T>, T::AccountId) = env.read_as()?;
+
+				let balance = crate::psp22::balance_of::<T>(id, owner);
+				write_output(&mut env, func_id, &balance.encode())
+			},
+
+			// PSP22 allowance
+			#[cfg(feature = "ext-psp22")]
+			0x602 => {
+				env.charge_weight(T::DbWeight::get().reads(1))?;
+
+				let mut env = env.buf_in_buf_out();
+				let len = <(AssetIdOf<T>, T::AccountId, T::AccountId)>::max_encoded_len() as u32;
+				if let Some(ret) = check_input_len(&mut env, func_id, len) {
+					return ret
+				}
+				let (id, owner, spender): (AssetIdOf<T>, T::AccountId, T::AccountId) =
+					env.read_as()?;
+
+				let allowance = crate::psp22::allowance::<T>(id, owner, spender);
+				write_output(&mut env, func_id, &allowance.encode())
+			},
+
+			// PSP22Metadata token_name, token_symbol and token_decimals in one call
+			#[cfg(feature = "ext-psp22")]
+			0x603 => {
+				env.charge_weight(T::DbWeight::get().reads(1))?;
+
+				let mut env = env.buf_in_buf_out();
+				let len = AssetIdOf::<T>::max_encoded_len() as u32;
+				if let Some(ret) = check_input_len(&mut env, func_id, len) {
+					return ret
+				}
+				let id: AssetIdOf<T> = env.read_as()?;
+
+				write_output(&mut env, func_id, &crate::psp22::metadata::<T>(id).encode())
+			},
+
+			// PSP22 transfer from the contract
+			#[cfg(feature = "ext-psp22")]
+			0x604 => {
+				env.charge_weight(crate::psp22::transfer_weight::<T>())?;
+
+				let mut env = env.buf_in_buf_out();
+				let len = <(AssetIdOf<T>, T::AccountId, BalanceOf<T>)>::max_encoded_len() as u32;
+				if let Some(ret) = check_input_len(&mut env, func_id, len) {
+					return ret
+				}
+				let (id, to, value): (AssetIdOf<T>, T::AccountId, BalanceOf<T>) = env.read_as()?;
+
+				let result = crate::psp22::transfer::<T>(&contract, id, to, value);
+				assets_result(env.ext(), func_id, result)
+			},
+
+			// PSP22 transfer_from, with the contract as spender
+			#[cfg(feature = "ext-psp22")]
+			0x605 => {
+				env.charge_weight(crate::psp22::transfer_from_weight::<T>())?;
+
+				let mut env = env.buf_in_buf_out();
+				let len =
+					<(AssetIdOf<T>, T::AccountId, T::AccountId, BalanceOf<T>)>::max_encoded_len()
+						as u32;
+				if let Some(ret) = check_input_len(&mut env, func_id, len) {
+					return ret
+				}
+				let (id, from, to, value): (
+					AssetIdOf<T>,
+					T::AccountId,
+					T::AccountId,
+					BalanceOf<T>,
+				) = env.read_as()?;
+
+				let result = crate::psp22::transfer_from::<T>(&contract, id, from, to, value);
+				assets_result(env.ext(), func_id, result)
+			},
+
+			// PSP22 approve, replacing the allowance of the spender
+			#[cfg(feature = "ext-psp22")]
+			0x606 => {
+				env.charge_weight(crate::psp22::approve_weight::<T>())?;
+
+				let mut env = env.buf_in_buf_out();
+				let len = <(AssetIdOf<T>, T::AccountId, BalanceOf<T>)>::max_encoded_len() as u32;
+				if let Some(ret) = check_input_len(&mut env, func_id, len) {
+					return ret
+				}
+				let (id, spender, value): (AssetIdOf<T>, T::AccountId, BalanceOf<T>) =
+					env.read_as()?;
+
+				let result = crate::psp22::approve::<T>(&contract, id, spender, value);
+				assets_result(env.ext(), func_id, result)
+			},
+
+			// Create an asset owned by the contract
+			#[cfg(feature = "ext-psp22")]
+			0x607 => {
+				env.charge_weight(crate::psp22::create_weight::<T>())?;
+
+				let mut env = env.buf_in_buf_out();
+				let len = <(AssetIdOf<T>, BalanceOf<T>)>::max_encoded_len() as u32;
+				if let Some(ret) = check_input_len(&mut env, func_id, len) {
+					return ret
+				}
+				let (id, min_balance): (AssetIdOf<T>, BalanceOf<T>) = env.read_as()?;
+
+				let result = crate::psp22::create::<T>(&contract, id, min_balance);
+				assets_result(env.ext(), func_id, result)
+			},
+
+			// Set the name, symbol and decimals of an asset owned by the contract
+			#[cfg(feature = "ext-psp22")]
+			0x608 => {
+				let mut env = env.buf_in_buf_out();
+				let len = env.in_len();
+				let max_len = crate::psp22::set_metadata_max_len::<T>();
+				if len > max_len {
+					debug_message(
+						env.ext(),
+						func_id,
+						&format!("expected at most {max_len} input bytes, got {len}"),
+					);
+					return fail(env.ext(), func_id, ReturnCode::BadInputLength)
+				}
+				let limit = <T as pallet_assets::Config>::StringLimit::get();
+				let charged =
+					env.charge_weight(crate::psp22::set_metadata_weight::<T>(limit, limit))?;
+
+				let (id, name, symbol, decimals): (AssetIdOf<T>, Vec<u8>, Vec<u8>, u8) =
+					env.read_as_unbounded(len)?;
+				// Longer strings are rejected by `pallet-assets`.
+				env.adjust_weight(
+					charged,
+					crate::psp22::set_metadata_weight::<T>(
+						(name.len() as u32).min(limit),
+						(symbol.len() as u32).min(limit),
+					),
+				);
+
+				let result = crate::psp22::set_metadata::<T>(&contract, id, name, symbol, decimals);
+				assets_result(env.ext(), func_id, result)
+			},
+
+			// Mint an asset the contract is the issuer of
+			#[cfg(feature = "ext-psp22")]
+			0x609 => {
+				env.charge_weight(crate::psp22::mint_weight::<T>())?;
+
+				let mut env = env.buf_in_buf_out();
+				let len = <(AssetIdOf<T>, T::AccountId, BalanceOf<T>)>::max_encoded_len() as u32;
+				if let Some(ret) = check_input_len(&mut env, func_id, len) {
+					return ret
+				}
+				let (id, to, value): (AssetIdOf<T>, T::AccountId, BalanceOf<T>) = env.read_as()?;
+
+				let result = crate::psp22::mint::<T>(&contract, id, to, value);
+				assets_result(env.ext(), func_id, result)
+			},
+
+			// The Ethereum address of an account
+			#[cfg(feature = "ext-mapping")]
+			0x700 => {
+				env.charge_weight(T::DbWeight::get().reads(1))?;
+
+				let mut env = env.buf_in_buf_out();
+				if let Some(ret) = check_input_len(&mut env, func_id, 32) {
+					return ret
+				}
+				let account: [u8; 32] = env.read_as()?;
+
+				let address = AccountMapping::<T>::address_of(&account.into());
+				write_output(&mut env, func_id, address.as_bytes())
+			},
+
+			// The account of an Ethereum address
+			#[cfg(feature = "ext-mapping")]
+			0x701 => {
+				env.charge_weight(T::DbWeight::get().reads(1))?;
+
+				let mut env = env.buf_in_buf_out();
+				if let Some(ret) = check_input_len(&mut env, func_id, 20) {
+					return ret
+				}
+				let address: [u8; 20] = env.read_as()?;
+
+				let account = AccountMapping::<T>::account_of(&address.into());
+				write_output(&mut env, func_id, &account.encode())
+			},
+
+			_ => fail(env.ext(), func_id, ReturnCode::UnknownFunction),
+		}
+	}
+
+	fn enabled() -> bool {
+		true
+	}
+}
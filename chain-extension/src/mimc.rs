@@ -3,11 +3,13 @@
 /// Source: https://github.com/hideyour-cash/hideyour-cash/tree/main/packages/contract-libraries/near_mimc
 use ff_wasm_unknown_unknown::PrimeField;
 
+use crate::InvalidArgument;
+
 #[derive(PrimeField)]
 #[PrimeFieldModulus = "21888242871839275222246405745257275088548364400416034343698204186575808495617"]
 #[PrimeFieldGenerator = "7"]
 #[PrimeFieldReprEndianness = "little"]
-pub struct Fp([u64; 4]);
+pub(crate) struct Fp([u64; 4]);
 
 impl Fp {
 	pub const fn dangerous_new(limbs: [u64; 4]) -> Self {
@@ -15,12 +17,30 @@ impl Fp {
 	}
 }
 
-impl From<[u8; 32]> for Fp {
-	fn from(val: [u8; 32]) -> Self {
-		Fp::from_repr(FpRepr(val)).unwrap()
+/// Decode a little-endian word, which must be smaller than the field modulus.
+impl TryFrom<[u8; 32]> for Fp {
+	type Error = InvalidArgument;
+
+	fn try_from(val: [u8; 32]) -> Result<Self, Self::Error> {
+		Option::from(Fp::from_repr(FpRepr(val))).ok_or(InvalidArgument::NotInField)
 	}
 }
 
+/// Apply [`mimc_feistel`] to two field elements given as little-endian words.
+pub(crate) fn feistel(input: &[u8; 64]) -> Result<[u8; 64], InvalidArgument> {
+	let mut x_l = [0u8; 32];
+	let mut x_r = [0u8; 32];
+	x_l.copy_from_slice(&input[..32]);
+	x_r.copy_from_slice(&input[32..]);
+
+	let (x_l, x_r) = mimc_feistel(Fp::try_from(x_l)?, Fp::try_from(x_r)?);
+
+	let mut output = [0u8; 64];
+	output[..32].copy_from_slice(&x_l.to_repr().0);
+	output[32..].copy_from_slice(&x_r.to_repr().0);
+	Ok(output)
+}
+
 pub(super) fn mimc_feistel(left: Fp, right: Fp) -> (Fp, Fp) {
 	let mut x_left = left;
 	let mut x_right = right;
@@ -41,7 +61,7 @@ macro_rules! fp_array {
     ( $($x:expr),* ) => ([$(Fp::dangerous_new($x)),*]);
 }
 
-pub const ROUND_CONSTANTS: [Fp; 220] = fp_array!(
+pub(crate) const ROUND_CONSTANTS: [Fp; 220] = fp_array!(
 	[0, 0, 0, 0],
 	[10834192717526305924, 16698740010641692384, 16538181000120689738, 377783109351314812],
 	[5221564414513982296, 8778502761099945834, 14713263936961097835, 3155493624356573779],
@@ -263,3 +283,101 @@ pub const ROUND_CONSTANTS: [Fp; 220] = fp_array!(
 	[10638244795695933810, 14332444851852596508, 2882072417649478317, 2429280932290573038],
 	[0, 0, 0, 0],
 );
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// A big-endian hex number as the little-endian representation of a field element.
+	fn fp(be: &str) -> Fp {
+		let mut repr = [0u8; 32];
+		for (i, byte) in repr.iter_mut().rev().enumerate() {
+			*byte = u8::from_str_radix(&be[2 * i..2 * i + 2], 16).unwrap();
+		}
+		Fp::try_from(repr).unwrap()
+	}
+
+	/// Little-endian words around the field modulus, and a few without any structure.
+	fn edge_words() -> Vec<[u8; 32]> {
+		let largest = (-Fp::from(1)).to_repr().0;
+		let mut words = vec![[0u8; 32], [0xff; 32], largest];
+		for delta in [1u8, 2] {
+			let mut word = largest;
+			word[0] += delta;
+			words.push(word);
+		}
+		let mut seed = 0x9e37_79b9_7f4a_7c15u64;
+		for _ in 0..64 {
+			let mut word = [0u8; 32];
+			for chunk in word.chunks_mut(8) {
+				seed ^= seed << 13;
+				seed ^= seed >> 7;
+				seed ^= seed << 17;
+				chunk.copy_from_slice(&seed.to_le_bytes());
+			}
+			words.push(word);
+		}
+		words
+	}
+
+	// Vectors from circomlib's `MiMCFeistel(220)` with `k = 0`.
+	#[test]
+	fn feistel_matches_circomlib() {
+		assert!(
+			mimc_feistel(Fp::from(0), Fp::from(0)) ==
+				(
+					fp("2027777a0db985664a19a476fa41bb311a34bf30e70eade27d3eb7ff691dba40"),
+					fp("2ecb76d9e45ae78bd3a2c416f313eca956284a9af91217709228db73c56a8aa7"),
+				)
+		);
+		assert!(
+			mimc_feistel(Fp::from(1), Fp::from(2)) ==
+				(
+					fp("29332b2bad024b96ae4482a1e1fc5ab172b954b5990cae754792046e45b61116"),
+					fp("2a51638a43a6219a4fd46f1a6437e39e9574275020d18c1d7de4c0788bf0dd59"),
+				)
+		);
+	}
+
+	// circomlibjs `mimcsponge.multiHash([1, 2])`, the sponge built from the Feistel permutation.
+	#[test]
+	fn sponge_matches_circomlib() {
+		let (x_l, x_r) = mimc_feistel(Fp::from(1), Fp::from(0));
+		let (x_l, _) = mimc_feistel(x_l + Fp::from(2), x_r);
+		assert!(x_l == fp("2bcea035a1251603f1ceaf73cd4ae89427c47075bb8e3a944039ff1e3d6d2a6f"));
+	}
+
+	#[test]
+	fn rejects_non_canonical_words() {
+		// p - 1 is the largest field element, p and above are not canonical.
+		let largest = (-Fp::from(1)).to_repr().0;
+		assert!(Fp::try_from(largest).is_ok());
+
+		let mut modulus = largest;
+		modulus[0] += 1;
+		assert!(matches!(Fp::try_from(modulus), Err(InvalidArgument::NotInField)));
+		assert!(matches!(Fp::try_from([0xff; 32]), Err(InvalidArgument::NotInField)));
+	}
+
+	#[test]
+	fn no_input_panics() {
+		let words = edge_words();
+		for x_l in &words {
+			for x_r in &words {
+				let input: [u8; 64] = [*x_l, *x_r].concat().try_into().unwrap();
+				let canonical = Fp::try_from(*x_l).is_ok() && Fp::try_from(*x_r).is_ok();
+				assert_eq!(feistel(&input).is_ok(), canonical);
+			}
+		}
+	}
+
+	#[test]
+	fn round_constants_are_circomlib_constants() {
+		assert!(ROUND_CONSTANTS[0] == Fp::from(0));
+		assert!(
+			ROUND_CONSTANTS[1] ==
+				fp("0fbe43c36a80e36d7c7c584d4f8f3759fb51f0d66065d8a227b688d12488c5d4")
+		);
+		assert!(ROUND_CONSTANTS[219] == Fp::from(0));
+	}
+}
@@ -0,0 +1,394 @@
+/// PLONK (KZG on BN254) proof verification
+///
+/// Follows the verifier that snarkjs generates for Solidity (`verifier_plonk.sol.ejs`), so
+/// proofs and verification keys exported by snarkjs verify unchanged. Like the other bn128
+/// functions, every field element and coordinate is passed as a 32 byte little-endian word and
+/// G2 points list the imaginary part of each coordinate first.
+///
+/// Input layout:
+///
+/// | Part          | Contents                                                         | Bytes    |
+/// |---------------|------------------------------------------------------------------|----------|
+/// | Verifying key | n, k1, k2, ω, Qm, Ql, Qr, Qo, Qc, S1, S2, S3 (G1), X₂ (G2)       | 768      |
+/// | Proof         | A, B, C, Z, T1, T2, T3, Wξ, Wξω (G1), a, b, c, s1, s2, zω (Fr)   | 768      |
+/// | Public inputs | one Fr element each                                              | 32 × k   |
+use bn::{pairing_batch, Fr, Group, Gt, G1, G2};
+use sp_std::vec::Vec;
+
+use crate::{
+	bn128::{read_fr, read_g2, read_point},
+	InvalidArgument,
+};
+
+/// Size of the encoded verifying key.
+pub(crate) const VK_LEN: usize = 4 * 32 + 8 * 64 + 128;
+/// Size of the encoded proof.
+pub(crate) const PROOF_LEN: usize = 9 * 64 + 6 * 32;
+/// Upper bound on the number of public inputs accepted by [`verify`].
+pub(crate) const MAX_PUBLIC_INPUTS: usize = 64;
+
+struct VerifyingKey {
+	power: u32,
+	n: Fr,
+	k1: Fr,
+	k2: Fr,
+	w: Fr,
+	qm: G1,
+	ql: G1,
+	qr: G1,
+	qo: G1,
+	qc: G1,
+	s1: G1,
+	s2: G1,
+	s3: G1,
+	x_2: G2,
+}
+
+struct Proof {
+	a: G1,
+	b: G1,
+	c: G1,
+	z: G1,
+	t1: G1,
+	t2: G1,
+	t3: G1,
+	wxi: G1,
+	wxiw: G1,
+	eval_a: Fr,
+	eval_b: Fr,
+	eval_c: Fr,
+	eval_s1: Fr,
+	eval_s2: Fr,
+	eval_zw: Fr,
+}
+
+/// The domain size must be a power of two that fits the BN254 scalar field's 2-adicity.
+fn read_domain_size(input: &[u8]) -> Result<(u32, Fr), InvalidArgument> {
+	let word = &input[..32];
+	if word[8..].iter().any(|b| *b != 0) {
+		return Err(InvalidArgument::NotInField)
+	}
+	let mut n = [0u8; 8];
+	n.copy_from_slice(&word[..8]);
+	let n = u64::from_le_bytes(n);
+	if !n.is_power_of_two() || n.trailing_zeros() > 28 {
+		return Err(InvalidArgument::NotInField)
+	}
+
+	let mut buf = [0u8; 32];
+	buf[24..].copy_from_slice(&n.to_be_bytes());
+	Ok((n.trailing_zeros(), Fr::from_slice(&buf)?))
+}
+
+fn read_vk(input: &[u8]) -> Result<VerifyingKey, InvalidArgument> {
+	let (power, n) = read_domain_size(input)?;
+	Ok(VerifyingKey {
+		power,
+		n,
+		k1: read_fr(input, 32)?,
+		k2: read_fr(input, 64)?,
+		w: read_fr(input, 96)?,
+		qm: read_point(input, 128)?,
+		ql: read_point(input, 192)?,
+		qr: read_point(input, 256)?,
+		qo: read_point(input, 320)?,
+		qc: read_point(input, 384)?,
+		s1: read_point(input, 448)?,
+		s2: read_point(input, 512)?,
+		s3: read_point(input, 576)?,
+		x_2: read_g2(input, 640)?,
+	})
+}
+
+fn read_proof(input: &[u8]) -> Result<Proof, InvalidArgument> {
+	Ok(Proof {
+		a: read_point(input, 0)?,
+		b: read_point(input, 64)?,
+		c: read_point(input, 128)?,
+		z: read_point(input, 192)?,
+		t1: read_point(input, 256)?,
+		t2: read_point(input, 320)?,
+		t3: read_point(input, 384)?,
+		wxi: read_point(input, 448)?,
+		wxiw: read_point(input, 512)?,
+		eval_a: read_fr(input, 576)?,
+		eval_b: read_fr(input, 608)?,
+		eval_c: read_fr(input, 640)?,
+		eval_s1: read_fr(input, 672)?,
+		eval_s2: read_fr(input, 704)?,
+		eval_zw: read_fr(input, 736)?,
+	})
+}
+
+/// Keccak based Fiat-Shamir transcript, hashing big-endian words like the Solidity verifier.
+#[derive(Default)]
+struct Transcript(Vec<u8>);
+
+impl Transcript {
+	/// Append little-endian words taken verbatim from the input.
+	fn append_words(&mut self, words: &[u8]) {
+		for word in words.chunks(32) {
+			self.0.extend(word.iter().rev());
+		}
+	}
+
+	fn append_fr(&mut self, fr: Fr) {
+		let mut buf = [0u8; 32];
+		fr.into_u256().to_big_endian(&mut buf).expect("buffer size is 32; qed");
+		self.0.extend_from_slice(&buf);
+	}
+
+	/// Hash everything appended so far into a challenge and reset the transcript.
+	fn challenge(&mut self) -> Fr {
+		let mut wide = [0u8; 64];
+		wide[32..].copy_from_slice(&sp_io::hashing::keccak_256(&self.0));
+		self.0.clear();
+		Fr::interpret(&wide)
+	}
+}
+
+/// Verify a snarkjs PLONK proof. `Ok(false)` means the proof is well formed but invalid.
+pub(crate) fn verify(input: &[u8]) -> Result<bool, InvalidArgument> {
+	let (vk_bytes, rest) = input.split_at(VK_LEN);
+	let (proof_bytes, pub_bytes) = rest.split_at(PROOF_LEN);
+
+	let vk = read_vk(vk_bytes)?;
+	let proof = read_proof(proof_bytes)?;
+	let public_inputs = (0..pub_bytes.len() / 32)
+		.map(|i| read_fr(pub_bytes, i * 32))
+		.collect::<Result<Vec<_>, _>>()?;
+
+	// Challenges
+	let mut transcript = Transcript::default();
+	transcript.append_words(&vk_bytes[128..640]);
+	transcript.append_words(pub_bytes);
+	transcript.append_words(&proof_bytes[..192]);
+	let beta = transcript.challenge();
+
+	transcript.append_fr(beta);
+	let gamma = transcript.challenge();
+
+	transcript.append_fr(beta);
+	transcript.append_fr(gamma);
+	transcript.append_words(&proof_bytes[192..256]);
+	let alpha = transcript.challenge();
+
+	transcript.append_fr(alpha);
+	transcript.append_words(&proof_bytes[256..448]);
+	let xi = transcript.challenge();
+
+	transcript.append_fr(xi);
+	transcript.append_words(&proof_bytes[576..]);
+	let v1 = transcript.challenge();
+
+	transcript.append_words(&proof_bytes[448..576]);
+	let u = transcript.challenge();
+
+	let v2 = v1 * v1;
+	let v3 = v2 * v1;
+	let v4 = v3 * v1;
+	let v5 = v4 * v1;
+	let alpha2 = alpha * alpha;
+
+	let mut xin = xi;
+	for _ in 0..vk.power {
+		xin = xin * xin;
+	}
+	let zh = xin - Fr::one();
+
+	// Lagrange polynomials L_i(ξ) = ωⁱ⁻¹·Zh(ξ) / (n·(ξ - ωⁱ⁻¹)), at least L_1 is always needed.
+	let mut lagrange = Vec::with_capacity(public_inputs.len().max(1));
+	let mut w = Fr::one();
+	for _ in 0..public_inputs.len().max(1) {
+		let Some(inv) = (vk.n * (xi - w)).inverse() else { return Ok(false) };
+		lagrange.push(w * zh * inv);
+		w = w * vk.w;
+	}
+	let l1 = lagrange[0];
+
+	let pi = public_inputs
+		.iter()
+		.zip(lagrange.iter())
+		.fold(Fr::zero(), |acc, (input, l)| acc - *l * *input);
+
+	// r0
+	let e3 = (proof.eval_a + beta * proof.eval_s1 + gamma) *
+		(proof.eval_b + beta * proof.eval_s2 + gamma) *
+		(proof.eval_c + gamma) *
+		proof.eval_zw *
+		alpha;
+	let r0 = pi - l1 * alpha2 - e3;
+
+	// [D]_1
+	let d1 = vk.qm * (proof.eval_a * proof.eval_b) +
+		vk.ql * proof.eval_a +
+		vk.qr * proof.eval_b +
+		vk.qo * proof.eval_c +
+		vk.qc;
+
+	let betaxi = beta * xi;
+	let d2a = (proof.eval_a + betaxi + gamma) *
+		(proof.eval_b + betaxi * vk.k1 + gamma) *
+		(proof.eval_c + betaxi * vk.k2 + gamma) *
+		alpha;
+	let d2b = l1 * alpha2;
+	let d2 = proof.z * (d2a + d2b + u);
+
+	let d3 = vk.s3 *
+		((proof.eval_a + beta * proof.eval_s1 + gamma) *
+			(proof.eval_b + beta * proof.eval_s2 + gamma) *
+			(alpha * beta * proof.eval_zw));
+
+	let d4 = (proof.t1 + proof.t2 * xin + proof.t3 * (xin * xin)) * zh;
+
+	let d = d1 + d2 - d3 - d4;
+
+	// [F]_1
+	let f = d + proof.a * v1 + proof.b * v2 + proof.c * v3 + vk.s1 * v4 + vk.s2 * v5;
+
+	// [E]_1
+	let s = -r0 +
+		proof.eval_a * v1 +
+		proof.eval_b * v2 +
+		proof.eval_c * v3 +
+		proof.eval_s1 * v4 +
+		proof.eval_s2 * v5 +
+		proof.eval_zw * u;
+	let e = G1::one() * s;
+
+	// e(-A1, X₂) · e(B1, [1]₂) == 1
+	let a1 = proof.wxi + proof.wxiw * u;
+	let b1 = proof.wxi * xi + proof.wxiw * (u * xi * vk.w) + f - e;
+
+	Ok(pairing_batch(&[(-a1, vk.x_2), (b1, G2::one())]) == Gt::one())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// Big-endian hex words as little-endian input words.
+	fn words(be: &[&str]) -> Vec<u8> {
+		be.iter()
+			.flat_map(|w| {
+				let mut word: Vec<u8> = (0..64)
+					.step_by(2)
+					.map(|i| u8::from_str_radix(&w[i..i + 2], 16).unwrap())
+					.collect();
+				word.reverse();
+				word
+			})
+			.collect()
+	}
+
+	// A proof for the circuit `x * y == z, x + y == s` with public z = 33 and s = 14 and a domain
+	// of 8 rows, produced by following snarkjs' `plonk setup` and `plonk prove` steps.
+	/// n, k1, k2, ω, Qm, Ql, Qr, Qo, Qc, S1, S2, S3, X₂.
+	const VK: [&str; 24] = [
+		"0000000000000000000000000000000000000000000000000000000000000008",
+		"0000000000000000000000000000000000000000000000000000000000000002",
+		"0000000000000000000000000000000000000000000000000000000000000003",
+		"2b337de1c8c14f22ec9b9e2f96afef3652627366f8170a0a948dad4ac1bd5e80",
+		"27bec7d0093e38807c9b1bfd263791a99a3149069e56b8ab46626f2fd02bfe3c",
+		"0864836e0b7b10591cfedf2a9e6319b15df2133361e89c23ff5cee5d37f4ee74",
+		"0568a2622fccdd3308292fa37556304f28b82801fe828fd9b0d05a4e363d790f",
+		"06bfd82dadcc4f947711052d4555d475ce36bbb35fcdbf308677cd23c3851509",
+		"08366af016ce0924decfdc6db4cc53db86197cbbb36ec36484a487cc3c5373e9",
+		"0f7ce3f6db67f65be3513873ed7106ea1cb2f0a0107180ca1cb873d4f29fa9a8",
+		"1ef69d3fa186a00c49002710dfa1a976de0dcab5dbc712da6dbeec278485a229",
+		"1b6eafb65f7176255555c846e683e808c62b26d0bce88e4020e53faae7723d26",
+		"0000000000000000000000000000000000000000000000000000000000000000",
+		"0000000000000000000000000000000000000000000000000000000000000000",
+		"123c60f93acf4c3c3fda8f8e3337cd29e8f7080a4a4cb9b16a31cbbffc3b00bc",
+		"2b80856e708ebc6ada5461c53a5de08b17d9cc19e9c4d366c191a7a327bb2efb",
+		"2759e4811b5b535bd6f30733fbe2f2b54e7b78e81f19fffbed022cc80887e90d",
+		"0ca97e452562ff7d27b41bc012b03e1bf99260bc9bc4d35c0b898e05f854b2be",
+		"09399a9bb12e3542ff9fab1309234436790e866a1ed6c6b2d4373fcd8f7d6ba5",
+		"00ada9c057053ec35906894ebcd9d77047b299ef4e5da73d346f6afa0b1cb3a4",
+		"041948e498a1729fb93c80ecde1bc26f31a45abb0fc45b0e3b8238d1cd0d0777",
+		"186b5f2a43cca433f58650d7d826511c55d2d140576e9bffc01ff84774d9a61c",
+		"2931e6c0c4967c74b9b34d976c178f1c43fc8b4b4f80a9ab7bd6db4ccf20ea8d",
+		"29013db982a43d771ff06f24aed4bf71206527e869b46dd1f4f5597e63e1893b",
+	];
+
+	/// A, B, C, Z, T1, T2, T3, Wξ, Wξω, a, b, c, s1, s2, zω.
+	const PROOF: [&str; 24] = [
+		"0c83350cbdb238f20aa5bb38153044036124832cbb795b87764fdd67f1fccac9",
+		"0976220e674f766cf5c311d9866f6d948263293475c23eb40fc960ba80f93873",
+		"00054fdb0ab71314a221fd0cadbe7e35ebbf581058b7d4a55d018763dcd76f0e",
+		"2d7645d6b3a7d198b689c5bbc18d0892260d22a15728ff3e72ef2a0efd554f4d",
+		"19a1e7b36818ff03b37d614e28779e0fd60641a7e36e52a8cae1d7ca4613c8d5",
+		"2bff81fd61b4372a51aa2a199c0ea8eeb69a932f824bf2b3d8be22acf4df43e0",
+		"1a17cba139a62a622e324c1468c00be017868e5b9dc8f9b6e71492da79dd32c3",
+		"098f2535278e0f0a3fbccd616dc61c7390143927e7b141877cf42e0505bc20e6",
+		"048b39afe69ae0736d7a1735e459d56e7c2e2b014d12db729b4a657c7d0605f0",
+		"00c8f4decf149d60266d1ec1f64d3ccc76d18010a2e6389875a255bd902c2531",
+		"23db71642d555249b55718c7c96a475c69b0437f2b2246a7b34f78fc1669d699",
+		"10ac133f03acbb8526645ae8976192b0b824b430a3858e7300938a30f4c1c8d4",
+		"16844d6bf744ce312f86b29f4ee1a5ef7f7d2def93f4fac3d21c5b54e3118abb",
+		"0db4bab213236f76ff42a760c74e4652cdb4416e5327425203c74eecb4e7d796",
+		"01a85a4676bed955d301b45a8378541ab65b38fb040b8a555d0950eb4cb88680",
+		"1652e9f2662abf6222e8e084115790b5ff80158d5d1424c7b5a000ca01ab37c4",
+		"0ebc26bc54aa7ef719104434e251fa86272883234c0db41da0cbfe74754550cc",
+		"23bd1ca9531aa6b1da9542f22652fa1058b321c0686030432625ba609c746ad1",
+		"1379f983e2149238c45a70708a11982914b0705e2999772ef0186a94276da08f",
+		"08191a64effdeecb357e19963fb11625b69a384a150edadbdeea1bb24770d278",
+		"139f86bc49dae050b76ae50ed34e03be0ab5c7bd3fa0e99f8582c1d59cf5c019",
+		"14b07d6262491e7a1a3ef5acf5d8206cf26d96d88cb1af6dc8abbe04b7e4d337",
+		"0da6288d454d735a444c87ee55c1c14b2e0b277a80e40384d760cf97c2c97f7e",
+		"1fe141bc5da66ad0dfce0348323782062ffaf2b7b29fe20ebb953a3d8b9949fe",
+	];
+
+	/// z, s.
+	const PUBLIC: [&str; 2] = [
+		"0000000000000000000000000000000000000000000000000000000000000021",
+		"000000000000000000000000000000000000000000000000000000000000000e",
+	];
+
+	fn valid_input() -> Vec<u8> {
+		[words(&VK), words(&PROOF), words(&PUBLIC)].concat()
+	}
+
+	#[test]
+	fn valid_proof_verifies() {
+		assert!(verify(&valid_input()).ok().unwrap());
+	}
+
+	#[test]
+	fn tampered_public_input_fails() {
+		let mut input = valid_input();
+		input[VK_LEN + PROOF_LEN + 32] ^= 1;
+		assert!(!verify(&input).ok().unwrap());
+
+		// Dropping an input changes the statement as well.
+		let input = valid_input()[..VK_LEN + PROOF_LEN + 32].to_vec();
+		assert!(!verify(&input).ok().unwrap());
+	}
+
+	#[test]
+	fn tampered_proof_fails() {
+		// eval_a
+		let mut input = valid_input();
+		input[VK_LEN + 576] ^= 1;
+		assert!(!verify(&input).ok().unwrap());
+
+		// Swap Wξ and Wξω.
+		let mut input = valid_input();
+		let (wxi, wxiw) = input[VK_LEN + 448..VK_LEN + 576].split_at_mut(64);
+		wxi.swap_with_slice(wxiw);
+		assert!(!verify(&input).ok().unwrap());
+	}
+
+	#[test]
+	fn off_curve_point_is_rejected() {
+		// A.y
+		let mut input = valid_input();
+		input[VK_LEN + 32] ^= 1;
+		assert!(matches!(verify(&input), Err(InvalidArgument::NotOnCurve)));
+
+		// Qm.y
+		let mut input = valid_input();
+		input[128 + 32] ^= 1;
+		assert!(matches!(verify(&input), Err(InvalidArgument::NotOnCurve)));
+	}
+}
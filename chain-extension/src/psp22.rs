@@ -0,0 +1,362 @@
+//! PSP22 access to the fungible assets of `pallet-assets`.
+//!
+//! Calls that change state are dispatched with the calling contract as signed origin, so the
+//! deposits and permission checks of `pallet-assets` apply as for any other account. A contract
+//! that creates an asset becomes its owner, admin, issuer and freezer.
+
+use codec::{Compact, CompactLen, MaxEncodedLen};
+use frame_support::{
+	dispatch::DispatchResult,
+	storage::with_storage_layer,
+	traits::{
+		fungibles::{approvals, metadata, Inspect},
+		Get,
+	},
+	weights::Weight,
+};
+use frame_system::{pallet_prelude::OriginFor, RawOrigin};
+use pallet_assets::WeightInfo;
+use sp_runtime::traits::{StaticLookup, Zero};
+use sp_std::vec::Vec;
+
+pub(crate) type AssetIdOf<T> = <T as pallet_assets::Config>::AssetId;
+pub(crate) type BalanceOf<T> = <T as pallet_assets::Config>::Balance;
+type Assets<T> = pallet_assets::Pallet<T>;
+type AssetsWeight<T> = <T as pallet_assets::Config>::WeightInfo;
+
+fn signed<T: pallet_assets::Config>(contract: &T::AccountId) -> OriginFor<T> {
+	RawOrigin::Signed(contract.clone()).into()
+}
+
+fn unlookup<T: pallet_assets::Config>(who: T::AccountId) -> <T::Lookup as StaticLookup>::Source {
+	T::Lookup::unlookup(who)
+}
+
+pub(crate) fn total_supply<T: pallet_assets::Config>(id: AssetIdOf<T>) -> BalanceOf<T> {
+	<Assets<T> as Inspect<T::AccountId>>::total_issuance(id)
+}
+
+pub(crate) fn balance_of<T: pallet_assets::Config>(
+	id: AssetIdOf<T>,
+	owner: T::AccountId,
+) -> BalanceOf<T> {
+	<Assets<T> as Inspect<T::AccountId>>::balance(id, &owner)
+}
+
+pub(crate) fn allowance<T: pallet_assets::Config>(
+	id: AssetIdOf<T>,
+	owner: T::AccountId,
+	spender: T::AccountId,
+) -> BalanceOf<T> {
+	<Assets<T> as approvals::Inspect<T::AccountId>>::allowance(id, &owner, &spender)
+}
+
+/// The name, symbol and decimals of an asset, empty for assets without metadata.
+pub(crate) fn metadata<T: pallet_assets::Config>(id: AssetIdOf<T>) -> (Vec<u8>, Vec<u8>, u8) {
+	(
+		<Assets<T> as metadata::Inspect<T::AccountId>>::name(id.clone()),
+		<Assets<T> as metadata::Inspect<T::AccountId>>::symbol(id.clone()),
+		<Assets<T> as metadata::Inspect<T::AccountId>>::decimals(id),
+	)
+}
+
+pub(crate) fn transfer<T: pallet_assets::Config>(
+	contract: &T::AccountId,
+	id: AssetIdOf<T>,
+	to: T::AccountId,
+	value: BalanceOf<T>,
+) -> DispatchResult {
+	with_storage_layer(|| {
+		Assets::<T>::transfer(signed::<T>(contract), id.into(), unlookup::<T>(to), value)
+	})
+}
+
+/// Transfer `value` of `from` to `to`, spending an approval of `from` for the contract.
+pub(crate) fn transfer_from<T: pallet_assets::Config>(
+	contract: &T::AccountId,
+	id: AssetIdOf<T>,
+	from: T::AccountId,
+	to: T::AccountId,
+	value: BalanceOf<T>,
+) -> DispatchResult {
+	with_storage_layer(|| {
+		Assets::<T>::transfer_approved(
+			signed::<T>(contract),
+			id.into(),
+			unlookup::<T>(from),
+			unlookup::<T>(to),
+			value,
+		)
+	})
+}
+
+/// Allow `spender` to transfer `value` of the contract's balance.
+///
+/// `approve_transfer` of `pallet-assets` adds to an existing approval, while PSP22 replaces it, so
+/// an existing approval is cancelled first.
+pub(crate) fn approve<T: pallet_assets::Config>(
+	contract: &T::AccountId,
+	id: AssetIdOf<T>,
+	spender: T::AccountId,
+	value: BalanceOf<T>,
+) -> DispatchResult {
+	with_storage_layer(|| {
+		if !allowance::<T>(id.clone(), contract.clone(), spender.clone()).is_zero() {
+			Assets::<T>::cancel_approval(
+				signed::<T>(contract),
+				id.clone().into(),
+				unlookup::<T>(spender.clone()),
+			)?;
+		}
+		if value.is_zero() {
+			return Ok(())
+		}
+		Assets::<T>::approve_transfer(
+			signed::<T>(contract),
+			id.into(),
+			unlookup::<T>(spender),
+			value,
+		)
+	})
+}
+
+/// Create an asset owned by the contract, which pays the asset deposit.
+pub(crate) fn create<T: pallet_assets::Config>(
+	contract: &T::AccountId,
+	id: AssetIdOf<T>,
+	min_balance: BalanceOf<T>,
+) -> DispatchResult {
+	with_storage_layer(|| {
+		Assets::<T>::create(
+			signed::<T>(contract),
+			id.into(),
+			unlookup::<T>(contract.clone()),
+			min_balance,
+		)
+	})
+}
+
+/// Set the metadata of an asset owned by the contract.
+pub(crate) fn set_metadata<T: pallet_assets::Config>(
+	contract: &T::AccountId,
+	id: AssetIdOf<T>,
+	name: Vec<u8>,
+	symbol: Vec<u8>,
+	decimals: u8,
+) -> DispatchResult {
+	with_storage_layer(|| {
+		Assets::<T>::set_metadata(signed::<T>(contract), id.into(), name, symbol, decimals)
+	})
+}
+
+/// Mint `value` to `to`, the contract must be the issuer of the asset.
+pub(crate) fn mint<T: pallet_assets::Config>(
+	contract: &T::AccountId,
+	id: AssetIdOf<T>,
+	to: T::AccountId,
+	value: BalanceOf<T>,
+) -> DispatchResult {
+	with_storage_layer(|| {
+		Assets::<T>::mint(signed::<T>(contract), id.into(), unlookup::<T>(to), value)
+	})
+}
+
+pub(crate) fn transfer_weight<T: pallet_assets::Config>() -> Weight {
+	AssetsWeight::<T>::transfer()
+}
+
+pub(crate) fn transfer_from_weight<T: pallet_assets::Config>() -> Weight {
+	AssetsWeight::<T>::transfer_approved()
+}
+
+pub(crate) fn approve_weight<T: pallet_assets::Config>() -> Weight {
+	T::DbWeight::get()
+		.reads(1)
+		.saturating_add(AssetsWeight::<T>::cancel_approval())
+		.saturating_add(AssetsWeight::<T>::approve_transfer())
+}
+
+pub(crate) fn create_weight<T: pallet_assets::Config>() -> Weight {
+	AssetsWeight::<T>::create()
+}
+
+/// The longest input of `set_metadata`, with a name and symbol of `StringLimit` bytes each.
+pub(crate) fn set_metadata_max_len<T: pallet_assets::Config>() -> u32 {
+	let limit = T::StringLimit::get();
+	let string = Compact::<u32>::compact_len(&limit) as u32 + limit;
+	AssetIdOf::<T>::max_encoded_len() as u32 + 2 * string + 1
+}
+
+pub(crate) fn set_metadata_weight<T: pallet_assets::Config>(name: u32, symbol: u32) -> Weight {
+	AssetsWeight::<T>::set_metadata(name, symbol)
+}
+
+pub(crate) fn mint_weight<T: pallet_assets::Config>() -> Weight {
+	AssetsWeight::<T>::mint()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use codec::Encode;
+	use frame_support::{
+		assert_noop, assert_ok, construct_runtime, derive_impl,
+		traits::{AsEnsureOriginWithArg, ConstU32, ConstU64},
+	};
+	use frame_system::{EnsureRoot, EnsureSigned};
+	use pallet_assets::Error;
+	use sp_runtime::BuildStorage;
+
+	type Block = frame_system::mocking::MockBlock<Test>;
+
+	construct_runtime!(
+		pub enum Test {
+			System: frame_system,
+			Balances: pallet_balances,
+			Assets: pallet_assets,
+		}
+	);
+
+	#[derive_impl(frame_system::config_preludes::TestDefaultConfig as frame_system::DefaultConfig)]
+	impl frame_system::Config for Test {
+		type Block = Block;
+		type AccountData = pallet_balances::AccountData<u64>;
+	}
+
+	#[derive_impl(pallet_balances::config_preludes::TestDefaultConfig as pallet_balances::DefaultConfig)]
+	impl pallet_balances::Config for Test {
+		type AccountStore = System;
+	}
+
+	const STRING_LIMIT: u32 = 8;
+
+	impl pallet_assets::Config for Test {
+		type RuntimeEvent = RuntimeEvent;
+		type Balance = u64;
+		type AssetId = u32;
+		type AssetIdParameter = codec::Compact<u32>;
+		type CreateOrigin = AsEnsureOriginWithArg<EnsureSigned<u64>>;
+		type Currency = Balances;
+		type ForceOrigin = EnsureRoot<u64>;
+		type AssetDeposit = ConstU64<1>;
+		type AssetAccountDeposit = ConstU64<1>;
+		type MetadataDepositBase = ConstU64<1>;
+		type MetadataDepositPerByte = ConstU64<1>;
+		type ApprovalDeposit = ConstU64<1>;
+		type StringLimit = ConstU32<STRING_LIMIT>;
+		type Freezer = ();
+		type Extra = ();
+		type WeightInfo = ();
+		type RemoveItemsLimit = ConstU32<1000>;
+		type CallbackHandle = ();
+	}
+
+	const CONTRACT: u64 = 1;
+	const OTHER: u64 = 2;
+	const SPENDER: u64 = 3;
+	const ID: u32 = 7;
+
+	/// Asset `ID`, created by `CONTRACT`.
+	fn new_test_ext() -> sp_io::TestExternalities {
+		let mut storage = frame_system::GenesisConfig::<Test>::default().build_storage().unwrap();
+		pallet_balances::GenesisConfig::<Test> {
+			balances: vec![(CONTRACT, 1_000), (OTHER, 1_000), (SPENDER, 1_000)],
+		}
+		.assimilate_storage(&mut storage)
+		.unwrap();
+		let mut ext = sp_io::TestExternalities::new(storage);
+		ext.execute_with(|| {
+			System::set_block_number(1);
+			assert_ok!(create::<Test>(&CONTRACT, ID, 1));
+		});
+		ext
+	}
+
+	#[test]
+	fn only_the_issuer_mints() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(mint::<Test>(&CONTRACT, ID, OTHER, 100));
+			assert_eq!(balance_of::<Test>(ID, OTHER), 100);
+			assert_eq!(total_supply::<Test>(ID), 100);
+
+			assert_noop!(mint::<Test>(&OTHER, ID, OTHER, 100), Error::<Test>::NoPermission);
+		});
+	}
+
+	#[test]
+	fn only_the_owner_sets_metadata() {
+		new_test_ext().execute_with(|| {
+			assert_eq!(metadata::<Test>(ID), (vec![], vec![], 0));
+			assert_noop!(
+				set_metadata::<Test>(&OTHER, ID, b"Token".to_vec(), b"TKN".to_vec(), 10),
+				Error::<Test>::NoPermission
+			);
+
+			assert_ok!(set_metadata::<Test>(&CONTRACT, ID, b"Token".to_vec(), b"TKN".to_vec(), 10));
+			assert_eq!(metadata::<Test>(ID), (b"Token".to_vec(), b"TKN".to_vec(), 10));
+
+			let too_long = vec![b'a'; STRING_LIMIT as usize + 1];
+			assert_noop!(
+				set_metadata::<Test>(&CONTRACT, ID, too_long, b"TKN".to_vec(), 10),
+				Error::<Test>::BadMetadata
+			);
+		});
+	}
+
+	#[test]
+	fn set_metadata_max_len_fits_the_longest_input() {
+		let string = vec![b'a'; STRING_LIMIT as usize];
+		let longest = (ID, string.clone(), string, u8::MAX);
+		assert_eq!(set_metadata_max_len::<Test>(), longest.encode().len() as u32);
+	}
+
+	#[test]
+	fn transfer_moves_the_contracts_balance() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(mint::<Test>(&CONTRACT, ID, CONTRACT, 100));
+			assert_ok!(transfer::<Test>(&CONTRACT, ID, OTHER, 40));
+			assert_eq!(balance_of::<Test>(ID, CONTRACT), 60);
+			assert_eq!(balance_of::<Test>(ID, OTHER), 40);
+
+			assert_noop!(transfer::<Test>(&CONTRACT, ID, OTHER, 61), Error::<Test>::BalanceLow);
+		});
+	}
+
+	#[test]
+	fn approve_replaces_the_allowance() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(mint::<Test>(&CONTRACT, ID, CONTRACT, 100));
+			assert_ok!(approve::<Test>(&CONTRACT, ID, SPENDER, 30));
+			assert_ok!(approve::<Test>(&CONTRACT, ID, SPENDER, 20));
+			assert_eq!(allowance::<Test>(ID, CONTRACT, SPENDER), 20);
+
+			assert_ok!(approve::<Test>(&CONTRACT, ID, SPENDER, 0));
+			assert_eq!(allowance::<Test>(ID, CONTRACT, SPENDER), 0);
+			// There is no approval left to cancel.
+			assert_ok!(approve::<Test>(&CONTRACT, ID, SPENDER, 0));
+		});
+	}
+
+	#[test]
+	fn transfer_from_spends_the_allowance() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(mint::<Test>(&CONTRACT, ID, OTHER, 100));
+			assert_ok!(approve::<Test>(&OTHER, ID, SPENDER, 30));
+
+			assert_noop!(
+				transfer_from::<Test>(&SPENDER, ID, OTHER, SPENDER, 31),
+				Error::<Test>::Unapproved
+			);
+			assert_ok!(transfer_from::<Test>(&SPENDER, ID, OTHER, SPENDER, 10));
+			assert_eq!(balance_of::<Test>(ID, OTHER), 90);
+			assert_eq!(balance_of::<Test>(ID, SPENDER), 10);
+			assert_eq!(allowance::<Test>(ID, OTHER, SPENDER), 20);
+
+			// The approval is for `SPENDER` only.
+			assert_noop!(
+				transfer_from::<Test>(&CONTRACT, ID, OTHER, CONTRACT, 10),
+				Error::<Test>::Unapproved
+			);
+		});
+	}
+}
@@ -0,0 +1,39 @@
+//! Dry runs of code uploaded with `Determinism::Relaxed`.
+//!
+//! `ContractsApi::call` runs with `Determinism::Enforced`, so it can not call code that may only
+//! run off-chain, e.g. because it uses floats.
+
+use frame_support::{traits::fungible::Inspect, weights::Weight};
+use pallet_contracts::{CollectEvents, ContractExecResult, DebugInfo, Determinism};
+use sp_std::vec::Vec;
+
+use crate::without_events;
+
+type BalanceOf<T> = <<T as pallet_contracts::Config>::Currency as Inspect<
+	<T as frame_system::Config>::AccountId,
+>>::Balance;
+
+/// `ContractsApi::call` with `determinism`. Events are not collected.
+#[allow(clippy::too_many_arguments)]
+pub fn call_with_determinism<T: pallet_contracts::Config>(
+	origin: T::AccountId,
+	dest: T::AccountId,
+	value: BalanceOf<T>,
+	gas_limit: Weight,
+	storage_deposit_limit: Option<BalanceOf<T>>,
+	input_data: Vec<u8>,
+	determinism: Determinism,
+	debug: DebugInfo,
+) -> ContractExecResult<BalanceOf<T>, ()> {
+	without_events(pallet_contracts::Pallet::<T>::bare_call(
+		origin,
+		dest,
+		value,
+		gas_limit,
+		storage_deposit_limit,
+		input_data,
+		debug,
+		CollectEvents::Skip,
+		determinism,
+	))
+}
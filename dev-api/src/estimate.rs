@@ -0,0 +1,207 @@
+//! The smallest gas limit and the storage deposit a call or instantiation needs.
+//!
+//! The `gas_required` of a dry run is the gas the dry run needed with the limit it was given. A
+//! smaller limit can change the gas needed, e.g. when a contract forwards a share of the gas left
+//! to a reentrant call, so it is not a safe limit. The estimate searches the smallest limit that
+//! succeeds instead, with every dry run rolled back.
+
+use codec::{Decode, Encode};
+use frame_support::{
+	storage::{with_transaction_unchecked, TransactionOutcome},
+	traits::fungible::Inspect,
+	weights::Weight,
+};
+use pallet_contracts::{
+	Code, CollectEvents, ContractResult, DebugInfo, Determinism, ExecReturnValue, StorageDeposit,
+};
+use scale_info::TypeInfo;
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+use sp_runtime::{DispatchError, RuntimeDebug};
+use sp_std::vec::Vec;
+
+use crate::Deposit;
+
+type BalanceOf<T> = <<T as pallet_contracts::Config>::Currency as Inspect<
+	<T as frame_system::Config>::AccountId,
+>>::Balance;
+
+/// The limits to submit a call or instantiation with.
+#[derive(Clone, Encode, Decode, PartialEq, Eq, RuntimeDebug, TypeInfo)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct GasEstimate<Balance> {
+	/// The smallest gas limit the dry run succeeds with.
+	pub gas_limit: Weight,
+	/// The storage deposit charged or refunded with that gas limit.
+	pub storage_deposit: Deposit<Balance>,
+	/// The error the dry run failed with at the maximum gas limit, or `ContractReverted`. The
+	/// limits are the ones reported by that dry run then.
+	pub error: Option<DispatchError>,
+}
+
+/// The parts of a dry run the estimate looks at.
+struct DryRun<Balance> {
+	gas_consumed: Weight,
+	gas_required: Weight,
+	storage_deposit: StorageDeposit<Balance>,
+	result: Result<(), DispatchError>,
+}
+
+/// The smallest value in `lo..=hi` that `succeeds`, assuming `succeeds(hi)` and that larger values
+/// succeed when a smaller one does.
+fn smallest(mut lo: u64, mut hi: u64, mut succeeds: impl FnMut(u64) -> bool) -> u64 {
+	while lo < hi {
+		let mid = lo + (hi - lo) / 2;
+		if succeeds(mid) {
+			hi = mid;
+		} else {
+			lo = mid + 1;
+		}
+	}
+	hi
+}
+
+/// Search the smallest `ref_time` and then the smallest `proof_size` that `dry_run` succeeds with,
+/// up to `max`.
+fn estimate<Balance>(
+	max: Weight,
+	dry_run: impl Fn(Weight) -> DryRun<Balance>,
+) -> GasEstimate<Balance> {
+	let baseline = dry_run(max);
+	if let Err(err) = baseline.result {
+		return GasEstimate {
+			gas_limit: baseline.gas_required,
+			storage_deposit: baseline.storage_deposit.into(),
+			error: Some(err),
+		}
+	}
+
+	let lo = baseline.gas_consumed;
+	let succeeds = |limit: Weight| dry_run(limit).result.is_ok();
+	// `gas_required` is usually enough, which saves most of the search.
+	let hi = if succeeds(baseline.gas_required) { baseline.gas_required } else { max };
+
+	let ref_time = smallest(lo.ref_time(), hi.ref_time(), |ref_time| {
+		succeeds(Weight::from_parts(ref_time, hi.proof_size()))
+	});
+	let proof_size = smallest(lo.proof_size(), hi.proof_size(), |proof_size| {
+		succeeds(Weight::from_parts(ref_time, proof_size))
+	});
+
+	let gas_limit = Weight::from_parts(ref_time, proof_size);
+	let storage_deposit = dry_run(gas_limit).storage_deposit.into();
+	GasEstimate { gas_limit, storage_deposit, error: None }
+}
+
+fn summarize<T: pallet_contracts::Config, EventRecord>(
+	result: ContractResult<Result<ExecReturnValue, DispatchError>, BalanceOf<T>, EventRecord>,
+) -> DryRun<BalanceOf<T>> {
+	DryRun {
+		gas_consumed: result.gas_consumed,
+		gas_required: result.gas_required,
+		storage_deposit: result.storage_deposit,
+		result: match result.result {
+			Ok(output) if output.did_revert() =>
+				Err(pallet_contracts::Error::<T>::ContractReverted.into()),
+			Ok(_) => Ok(()),
+			Err(err) => Err(err),
+		},
+	}
+}
+
+/// Estimate the limits of a call like `ContractsApi::call`, searching gas limits up to
+/// `max_gas_limit`.
+pub fn estimate_call<T: pallet_contracts::Config>(
+	origin: T::AccountId,
+	dest: T::AccountId,
+	value: BalanceOf<T>,
+	max_gas_limit: Weight,
+	storage_deposit_limit: Option<BalanceOf<T>>,
+	input_data: Vec<u8>,
+) -> GasEstimate<BalanceOf<T>> {
+	estimate(max_gas_limit, |gas_limit| {
+		with_transaction_unchecked(|| {
+			TransactionOutcome::Rollback(summarize::<T, _>(pallet_contracts::Pallet::<T>::bare_call(
+				origin.clone(),
+				dest.clone(),
+				value,
+				gas_limit,
+				storage_deposit_limit,
+				input_data.clone(),
+				DebugInfo::Skip,
+				CollectEvents::Skip,
+				Determinism::Enforced,
+			)))
+		})
+	})
+}
+
+/// Estimate the limits of an instantiation like `ContractsApi::instantiate`, searching gas limits
+/// up to `max_gas_limit`.
+pub fn estimate_instantiate<T: pallet_contracts::Config>(
+	origin: T::AccountId,
+	value: BalanceOf<T>,
+	max_gas_limit: Weight,
+	storage_deposit_limit: Option<BalanceOf<T>>,
+	code: Code<T::Hash>,
+	data: Vec<u8>,
+	salt: Vec<u8>,
+) -> GasEstimate<BalanceOf<T>> {
+	estimate(max_gas_limit, |gas_limit| {
+		with_transaction_unchecked(|| {
+			let result = pallet_contracts::Pallet::<T>::bare_instantiate(
+				origin.clone(),
+				value,
+				gas_limit,
+				storage_deposit_limit,
+				code.clone(),
+				data.clone(),
+				salt.clone(),
+				DebugInfo::Skip,
+				CollectEvents::Skip,
+			);
+			let result = ContractResult {
+				gas_consumed: result.gas_consumed,
+				gas_required: result.gas_required,
+				storage_deposit: result.storage_deposit,
+				debug_message: result.debug_message,
+				result: result.result.map(|instantiated| instantiated.result),
+				events: result.events,
+			};
+			TransactionOutcome::Rollback(summarize::<T, _>(result))
+		})
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn smallest_finds_the_boundary() {
+		assert_eq!(smallest(0, 100, |x| x >= 37), 37);
+		assert_eq!(smallest(37, 100, |x| x >= 37), 37);
+		assert_eq!(smallest(0, 100, |x| x >= 100), 100);
+	}
+
+	#[test]
+	fn estimate_searches_both_dimensions() {
+		let needed = Weight::from_parts(1_234, 567);
+		let estimate = estimate(Weight::from_parts(1_000_000, 1_000_000), |limit| DryRun::<u64> {
+			gas_consumed: Weight::from_parts(1_000, 500),
+			// Less than needed, like a call that forwards a share of the gas left.
+			gas_required: Weight::from_parts(1_100, 550),
+			storage_deposit: StorageDeposit::Charge(10),
+			result: if limit.all_gte(needed) {
+				Ok(())
+			} else {
+				Err(DispatchError::Other("OutOfGas"))
+			},
+		});
+		assert_eq!(
+			estimate,
+			GasEstimate { gas_limit: needed, storage_deposit: Deposit::Charge(10), error: None }
+		);
+	}
+}
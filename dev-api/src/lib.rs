@@ -0,0 +1,153 @@
+//! # Contracts Dev API
+//!
+//! Runtime APIs for developing and testing contracts, on top of the dry runs of
+//! `pallet_contracts::ContractsApi`. Both runtimes implement [`ContractsDevApi`], and the node
+//! serves it through its `contracts_*` RPC methods.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+mod determinism;
+mod estimate;
+#[cfg(test)]
+mod mock;
+mod overrides;
+mod profile;
+mod tracing;
+
+pub use determinism::call_with_determinism;
+pub use estimate::{estimate_call, estimate_instantiate, GasEstimate};
+pub use overrides::{
+	call_with_overrides, instantiate_with_overrides, CodeOverride, StateOverrides, StateOverridesOf,
+};
+pub use profile::{
+	chain_extension_entered, chain_extension_returned, profile_call, CallProfile,
+	ChainExtensionCost, CostCategory, ProfiledSchedule,
+};
+pub use tracing::{
+	trace_call, CallOutcome, CallTrace, CallTracer, Deposit, EntryPoint, TraceSpan, TracedCall,
+};
+
+/// The type of gas limits in the API.
+pub use frame_support::weights::Weight;
+/// The code of contracts to instantiate.
+pub use pallet_contracts::Code;
+/// Whether dry runs may execute code that is not deterministic.
+pub use pallet_contracts::Determinism;
+
+use codec::Codec;
+use pallet_contracts::{ContractExecResult, ContractInstantiateResult, ContractResult};
+use sp_std::vec::Vec;
+
+sp_api::decl_runtime_apis! {
+	/// Dry runs that give more insight into contract execution than `ContractsApi`.
+	#[api_version(5)]
+	pub trait ContractsDevApi<AccountId, Balance, BlockNumber, Hash>
+	where
+		AccountId: Codec,
+		Balance: Codec,
+		BlockNumber: Codec,
+		Hash: Codec,
+	{
+		/// Like `ContractsApi::call`, but returns the tree of all contract calls it made.
+		fn trace_call(
+			origin: AccountId,
+			dest: AccountId,
+			value: Balance,
+			gas_limit: Option<Weight>,
+			storage_deposit_limit: Option<Balance>,
+			input_data: Vec<u8>,
+		) -> TracedCall<AccountId, Balance>;
+
+		/// Like `ContractsApi::call`, but splits the gas consumed by the kind of cost.
+		#[api_version(2)]
+		fn profile_call(
+			origin: AccountId,
+			dest: AccountId,
+			value: Balance,
+			gas_limit: Option<Weight>,
+			storage_deposit_limit: Option<Balance>,
+			input_data: Vec<u8>,
+		) -> CallProfile;
+
+		/// Like `ContractsApi::call`, but against the state changed by `overrides`.
+		#[api_version(3)]
+		fn call_with_overrides(
+			origin: AccountId,
+			dest: AccountId,
+			value: Balance,
+			gas_limit: Option<Weight>,
+			storage_deposit_limit: Option<Balance>,
+			input_data: Vec<u8>,
+			overrides: StateOverrides<AccountId, Balance, Hash, BlockNumber>,
+		) -> ContractExecResult<Balance, ()>;
+
+		/// Like `ContractsApi::instantiate`, but against the state changed by `overrides`.
+		#[api_version(3)]
+		#[allow(clippy::too_many_arguments)]
+		fn instantiate_with_overrides(
+			origin: AccountId,
+			value: Balance,
+			gas_limit: Option<Weight>,
+			storage_deposit_limit: Option<Balance>,
+			code: Code<Hash>,
+			data: Vec<u8>,
+			salt: Vec<u8>,
+			overrides: StateOverrides<AccountId, Balance, Hash, BlockNumber>,
+		) -> ContractInstantiateResult<AccountId, Balance, ()>;
+
+		/// The smallest gas limit and the storage deposit a call like `ContractsApi::call` needs,
+		/// searching gas limits up to `gas_limit`.
+		#[api_version(4)]
+		fn estimate_call(
+			origin: AccountId,
+			dest: AccountId,
+			value: Balance,
+			gas_limit: Option<Weight>,
+			storage_deposit_limit: Option<Balance>,
+			input_data: Vec<u8>,
+		) -> GasEstimate<Balance>;
+
+		/// The smallest gas limit and the storage deposit an instantiation like
+		/// `ContractsApi::instantiate` needs, searching gas limits up to `gas_limit`.
+		#[api_version(4)]
+		fn estimate_instantiate(
+			origin: AccountId,
+			value: Balance,
+			gas_limit: Option<Weight>,
+			storage_deposit_limit: Option<Balance>,
+			code: Code<Hash>,
+			data: Vec<u8>,
+			salt: Vec<u8>,
+		) -> GasEstimate<Balance>;
+
+		/// Like `ContractsApi::call`, but with `determinism` instead of `Determinism::Enforced`.
+		///
+		/// With `Determinism::Relaxed`, the call may execute code uploaded with
+		/// `Determinism::Relaxed`, which can not be called on-chain.
+		#[api_version(5)]
+		#[allow(clippy::too_many_arguments)]
+		fn call_with_determinism(
+			origin: AccountId,
+			dest: AccountId,
+			value: Balance,
+			gas_limit: Option<Weight>,
+			storage_deposit_limit: Option<Balance>,
+			input_data: Vec<u8>,
+			determinism: Determinism,
+		) -> ContractExecResult<Balance, ()>;
+	}
+}
+
+/// `result` without the events, whose type differs between the runtimes.
+fn without_events<R, Balance, EventRecord>(
+	result: ContractResult<R, Balance, EventRecord>,
+) -> ContractResult<R, Balance, ()> {
+	ContractResult {
+		gas_consumed: result.gas_consumed,
+		gas_required: result.gas_required,
+		storage_deposit: result.storage_deposit,
+		debug_message: result.debug_message,
+		result: result.result,
+		events: None,
+	}
+}
@@ -0,0 +1,116 @@
+//! A runtime with `pallet-contracts` set up like in the contracts node runtimes, for the tests of
+//! the dry runs.
+
+use crate::{CallTracer, ProfiledSchedule};
+use frame_support::{
+	construct_runtime, derive_impl, parameter_types,
+	traits::{ConstBool, ConstU32, ConstU64, Nothing, Randomness},
+	weights::Weight,
+};
+use pallet_contracts::{Code, CollectEvents, DebugInfo};
+use sp_runtime::{testing::H256, traits::IdentityLookup, AccountId32, BuildStorage, Perbill};
+
+type Block = frame_system::mocking::MockBlock<Test>;
+
+construct_runtime!(
+	pub enum Test {
+		System: frame_system,
+		Balances: pallet_balances,
+		Timestamp: pallet_timestamp,
+		Contracts: pallet_contracts,
+	}
+);
+
+#[derive_impl(frame_system::config_preludes::TestDefaultConfig as frame_system::DefaultConfig)]
+impl frame_system::Config for Test {
+	type Block = Block;
+	type AccountId = AccountId32;
+	type Lookup = IdentityLookup<AccountId32>;
+	type AccountData = pallet_balances::AccountData<u64>;
+}
+
+#[derive_impl(pallet_balances::config_preludes::TestDefaultConfig as pallet_balances::DefaultConfig)]
+impl pallet_balances::Config for Test {
+	type AccountStore = System;
+}
+
+impl pallet_timestamp::Config for Test {
+	type Moment = u64;
+	type OnTimestampSet = ();
+	type MinimumPeriod = ConstU64<1>;
+	type WeightInfo = ();
+}
+
+pub struct TestRandomness;
+
+impl Randomness<H256, u64> for TestRandomness {
+	fn random(_subject: &[u8]) -> (H256, u64) {
+		(H256::zero(), 0)
+	}
+}
+
+parameter_types! {
+	pub Schedule: pallet_contracts::Schedule<Test> = Default::default();
+	pub const CodeHashLockupDepositPercent: Perbill = Perbill::from_percent(0);
+}
+
+impl pallet_contracts::Config for Test {
+	type Time = Timestamp;
+	type Randomness = TestRandomness;
+	type Currency = Balances;
+	type RuntimeEvent = RuntimeEvent;
+	type RuntimeCall = RuntimeCall;
+	type CallFilter = Nothing;
+	type DepositPerItem = ConstU64<1>;
+	type DepositPerByte = ConstU64<1>;
+	type CallStack = [pallet_contracts::Frame<Self>; 5];
+	type WeightPrice = ();
+	type WeightInfo = ();
+	type ChainExtension = ();
+	type Schedule = ProfiledSchedule<Schedule>;
+	type AddressGenerator = pallet_contracts::DefaultAddressGenerator;
+	type MaxCodeLen = ConstU32<{ 123 * 1024 }>;
+	type DefaultDepositLimit = ConstU64<10_000_000>;
+	type MaxStorageKeyLen = ConstU32<128>;
+	type MaxDebugBufferLen = ConstU32<{ 2 * 1024 * 1024 }>;
+	type UnsafeUnstableInterface = ConstBool<false>;
+	type CodeHashLockupDepositPercent = CodeHashLockupDepositPercent;
+	type MaxDelegateDependencies = ConstU32<32>;
+	type RuntimeHoldReason = RuntimeHoldReason;
+	type Environment = ();
+	type Debug = CallTracer;
+	type ApiVersion = ();
+	type Migrations = ();
+	type Xcm = ();
+}
+
+pub const ALICE: AccountId32 = AccountId32::new([1; 32]);
+pub const GAS_LIMIT: Weight = Weight::from_parts(100_000_000_000, 3 * 1024 * 1024);
+
+pub fn new_test_ext() -> sp_io::TestExternalities {
+	let mut storage = frame_system::GenesisConfig::<Test>::default().build_storage().unwrap();
+	pallet_balances::GenesisConfig::<Test> { balances: vec![(ALICE, 1_000_000_000_000)] }
+		.assimilate_storage(&mut storage)
+		.unwrap();
+	let mut ext = sp_io::TestExternalities::new(storage);
+	ext.execute_with(|| System::set_block_number(1));
+	ext
+}
+
+/// Instantiate the contract in the Wasm text format `wat` with `data`, and return its address.
+pub fn instantiate(wat: &str, data: Vec<u8>) -> AccountId32 {
+	Contracts::bare_instantiate(
+		ALICE,
+		0,
+		GAS_LIMIT,
+		None,
+		Code::Upload(wat::parse_str(wat).unwrap()),
+		data,
+		vec![],
+		DebugInfo::Skip,
+		CollectEvents::Skip,
+	)
+	.result
+	.unwrap()
+	.account_id
+}
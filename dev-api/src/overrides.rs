@@ -0,0 +1,329 @@
+//! Dry runs against a modified state, like `eth_call` with a state override set.
+//!
+//! The overrides are applied in a storage transaction that is rolled back after the dry run, so
+//! they never outlive it.
+
+use codec::{Decode, Encode};
+use frame_support::{
+	storage::{child, storage_prefix, unhashed, with_transaction_unchecked, TransactionOutcome},
+	traits::{
+		fungible::{Inspect, Mutate},
+		PalletInfoAccess,
+	},
+	weights::Weight,
+	Blake2_128Concat, StorageHasher, Twox64Concat,
+};
+use frame_system::{pallet_prelude::BlockNumberFor, EventRecord, RawOrigin};
+use pallet_contracts::{
+	Code, CollectEvents, ContractExecResult, ContractInstantiateResult, ContractResult, DebugInfo,
+	Determinism,
+};
+use scale_info::TypeInfo;
+use sp_core::storage::ChildInfo;
+use sp_runtime::{traits::StaticLookup, DispatchError, RuntimeDebug};
+use sp_std::vec::Vec;
+
+use crate::without_events;
+
+type BalanceOf<T> = <<T as pallet_contracts::Config>::Currency as Inspect<
+	<T as frame_system::Config>::AccountId,
+>>::Balance;
+type EventRecordOf<T> =
+	EventRecord<<T as frame_system::Config>::RuntimeEvent, <T as frame_system::Config>::Hash>;
+type DryRunResult<T, R, EventRecord> =
+	ContractResult<Result<R, DispatchError>, BalanceOf<T>, EventRecord>;
+
+/// The [`StateOverrides`] type of a runtime.
+pub type StateOverridesOf<T> = StateOverrides<
+	<T as frame_system::Config>::AccountId,
+	BalanceOf<T>,
+	<T as frame_system::Config>::Hash,
+	BlockNumberFor<T>,
+>;
+
+/// The code a contract is overridden with.
+#[derive(Clone, Encode, Decode, PartialEq, Eq, RuntimeDebug, TypeInfo)]
+pub enum CodeOverride<Hash> {
+	/// Code that was uploaded before.
+	Existing(Hash),
+	/// Code that is uploaded by the origin of the dry run first.
+	Upload(Vec<u8>),
+}
+
+/// Changes to the state that a dry run sees.
+#[derive(Clone, Encode, Decode, PartialEq, Eq, RuntimeDebug, TypeInfo)]
+pub struct StateOverrides<AccountId, Balance, Hash, BlockNumber> {
+	/// Balances to set.
+	pub balances: Vec<(AccountId, Balance)>,
+	/// The code of contracts to replace, keeping their storage.
+	pub code: Vec<(AccountId, CodeOverride<Hash>)>,
+	/// Storage items of contracts to set, or to remove if the value is `None`.
+	///
+	/// The keys are the ones contracts pass to the storage host functions. The storage deposit of
+	/// the contracts is not adjusted.
+	pub storage: Vec<(AccountId, Vec<u8>, Option<Vec<u8>>)>,
+	/// The block number to run at.
+	pub block_number: Option<BlockNumber>,
+	/// The timestamp to run at, in milliseconds.
+	pub timestamp: Option<u64>,
+}
+
+impl<AccountId, Balance, Hash, BlockNumber> Default
+	for StateOverrides<AccountId, Balance, Hash, BlockNumber>
+{
+	fn default() -> Self {
+		Self {
+			balances: Vec::new(),
+			code: Vec::new(),
+			storage: Vec::new(),
+			block_number: None,
+			timestamp: None,
+		}
+	}
+}
+
+/// The id of the child trie holding the storage of `contract`.
+///
+/// `pallet-contracts` does not expose it, so it is read from the start of the encoded
+/// `ContractInfo`. The tests check it against the storage of an instantiated contract.
+fn trie_id<T: pallet_contracts::Config>(contract: &T::AccountId) -> Option<Vec<u8>> {
+	let pallet = <pallet_contracts::Pallet<T> as PalletInfoAccess>::name();
+	let mut key = storage_prefix(pallet.as_bytes(), b"ContractInfoOf").to_vec();
+	key.extend(Twox64Concat::hash(&contract.encode()));
+	let info = unhashed::get_raw(&key)?;
+	Vec::<u8>::decode(&mut &info[..]).ok()
+}
+
+fn apply<T>(origin: &T::AccountId, overrides: StateOverridesOf<T>) -> Result<(), DispatchError>
+where
+	T: pallet_contracts::Config + pallet_timestamp::Config<Moment = u64>,
+{
+	let StateOverrides { balances, code, storage, block_number, timestamp } = overrides;
+
+	if let Some(number) = block_number {
+		frame_system::Pallet::<T>::set_block_number(number);
+	}
+	if let Some(now) = timestamp {
+		pallet_timestamp::Pallet::<T>::set_timestamp(now);
+	}
+	for (who, balance) in balances {
+		T::Currency::set_balance(&who, balance);
+	}
+	for (contract, code) in code {
+		let code_hash = match code {
+			CodeOverride::Existing(code_hash) => code_hash,
+			CodeOverride::Upload(code) =>
+				pallet_contracts::Pallet::<T>::bare_upload_code(
+					origin.clone(),
+					code,
+					None,
+					Determinism::Enforced,
+				)?
+				.code_hash,
+		};
+		pallet_contracts::Pallet::<T>::set_code(
+			RawOrigin::Root.into(),
+			T::Lookup::unlookup(contract),
+			code_hash,
+		)?;
+	}
+	for (contract, key, value) in storage {
+		let trie_id = trie_id::<T>(&contract)
+			.ok_or(DispatchError::Other("storage override of an account that is no contract"))?;
+		let child_info = ChildInfo::new_default(&trie_id);
+		let key = Blake2_128Concat::hash(&key);
+		match value {
+			Some(value) => child::put_raw(&child_info, &key, &value),
+			None => child::kill(&child_info, &key),
+		}
+	}
+	Ok(())
+}
+
+/// Apply `overrides`, run `dry_run` and roll everything back.
+fn with_overrides<T, R>(
+	origin: &T::AccountId,
+	overrides: StateOverridesOf<T>,
+	dry_run: impl FnOnce() -> DryRunResult<T, R, EventRecordOf<T>>,
+) -> DryRunResult<T, R, ()>
+where
+	T: pallet_contracts::Config + pallet_timestamp::Config<Moment = u64>,
+{
+	with_transaction_unchecked(|| {
+		let result = match apply::<T>(origin, overrides) {
+			Ok(()) => without_events(dry_run()),
+			Err(err) => ContractResult {
+				gas_consumed: Weight::zero(),
+				gas_required: Weight::zero(),
+				storage_deposit: Default::default(),
+				debug_message: b"failed to apply the state overrides".to_vec(),
+				result: Err(err),
+				events: None,
+			},
+		};
+		TransactionOutcome::Rollback(result)
+	})
+}
+
+/// `ContractsApi::call` against the state changed by `overrides`. Events are not collected.
+#[allow(clippy::too_many_arguments)]
+pub fn call_with_overrides<T>(
+	origin: T::AccountId,
+	dest: T::AccountId,
+	value: BalanceOf<T>,
+	gas_limit: Weight,
+	storage_deposit_limit: Option<BalanceOf<T>>,
+	input_data: Vec<u8>,
+	overrides: StateOverridesOf<T>,
+	debug: DebugInfo,
+) -> ContractExecResult<BalanceOf<T>, ()>
+where
+	T: pallet_contracts::Config + pallet_timestamp::Config<Moment = u64>,
+{
+	with_overrides::<T, _>(&origin.clone(), overrides, || {
+		pallet_contracts::Pallet::<T>::bare_call(
+			origin,
+			dest,
+			value,
+			gas_limit,
+			storage_deposit_limit,
+			input_data,
+			debug,
+			CollectEvents::Skip,
+			Determinism::Enforced,
+		)
+	})
+}
+
+/// `ContractsApi::instantiate` against the state changed by `overrides`. Events are not collected.
+#[allow(clippy::too_many_arguments)]
+pub fn instantiate_with_overrides<T>(
+	origin: T::AccountId,
+	value: BalanceOf<T>,
+	gas_limit: Weight,
+	storage_deposit_limit: Option<BalanceOf<T>>,
+	code: Code<T::Hash>,
+	data: Vec<u8>,
+	salt: Vec<u8>,
+	overrides: StateOverridesOf<T>,
+	debug: DebugInfo,
+) -> ContractInstantiateResult<T::AccountId, BalanceOf<T>, ()>
+where
+	T: pallet_contracts::Config + pallet_timestamp::Config<Moment = u64>,
+{
+	with_overrides::<T, _>(&origin.clone(), overrides, || {
+		pallet_contracts::Pallet::<T>::bare_instantiate(
+			origin,
+			value,
+			gas_limit,
+			storage_deposit_limit,
+			code,
+			data,
+			salt,
+			debug,
+			CollectEvents::Skip,
+		)
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::mock::{instantiate, new_test_ext, Contracts, Test, ALICE, GAS_LIMIT};
+
+	/// Stores a value under `key()` when deployed, and returns the value under that key when
+	/// called.
+	const CONTRACT: &str = r#"
+		(module
+			(import "seal0" "seal_set_storage" (func $seal_set_storage (param i32 i32 i32)))
+			(import "seal0" "seal_get_storage" (func $seal_get_storage (param i32 i32 i32) (result i32)))
+			(import "seal0" "seal_return" (func $seal_return (param i32 i32 i32)))
+			(import "env" "memory" (memory 1 1))
+
+			;; [0, 32) the key, [32, 36) the length of the value buffer, [36, 100) the value
+			(data (i32.const 0) "\01")
+			(data (i32.const 32) "\40")
+			(data (i32.const 36) "set on deploy")
+
+			(func (export "deploy")
+				(call $seal_set_storage (i32.const 0) (i32.const 36) (i32.const 13))
+			)
+
+			(func (export "call")
+				(drop (call $seal_get_storage (i32.const 0) (i32.const 36) (i32.const 32)))
+				(call $seal_return (i32.const 0) (i32.const 36) (i32.load (i32.const 32)))
+			)
+		)
+	"#;
+
+	fn key() -> Vec<u8> {
+		let mut key = vec![0; 32];
+		key[0] = 1;
+		key
+	}
+
+	fn call(
+		contract: &<Test as frame_system::Config>::AccountId,
+		overrides: StateOverridesOf<Test>,
+	) -> ContractExecResult<u64, ()> {
+		call_with_overrides::<Test>(
+			ALICE,
+			contract.clone(),
+			0,
+			GAS_LIMIT,
+			None,
+			vec![],
+			overrides,
+			DebugInfo::Skip,
+		)
+	}
+
+	#[test]
+	fn trie_id_of_instantiated_contract() {
+		new_test_ext().execute_with(|| {
+			let contract = instantiate(CONTRACT, vec![]);
+			let trie_id = trie_id::<Test>(&contract).unwrap();
+
+			// The child trie holds what the contract stored.
+			let child_info = ChildInfo::new_default(&trie_id);
+			let stored = child::get_raw(&child_info, &Blake2_128Concat::hash(&key()));
+			assert_eq!(stored.as_deref(), Some(&b"set on deploy"[..]));
+			assert_eq!(Contracts::get_storage(contract, key()), Ok(stored));
+
+			assert_eq!(trie_id::<Test>(&ALICE), None);
+		});
+	}
+
+	#[test]
+	fn storage_override_is_seen_and_rolled_back() {
+		new_test_ext().execute_with(|| {
+			let contract = instantiate(CONTRACT, vec![]);
+			assert_eq!(call(&contract, Default::default()).result.unwrap().data, b"set on deploy");
+
+			let overrides = StateOverrides {
+				storage: vec![(contract.clone(), key(), Some(b"overridden".to_vec()))],
+				..Default::default()
+			};
+			assert_eq!(call(&contract, overrides).result.unwrap().data, b"overridden");
+			assert_eq!(
+				Contracts::get_storage(contract, key()),
+				Ok(Some(b"set on deploy".to_vec()))
+			);
+		});
+	}
+
+	#[test]
+	fn storage_override_of_account_without_contract_fails() {
+		new_test_ext().execute_with(|| {
+			let contract = instantiate(CONTRACT, vec![]);
+			let overrides = StateOverrides {
+				storage: vec![(ALICE, key(), Some(b"overridden".to_vec()))],
+				..Default::default()
+			};
+			assert_eq!(
+				call(&contract, overrides).result,
+				Err(DispatchError::Other("storage override of an account that is no contract"))
+			);
+		});
+	}
+}
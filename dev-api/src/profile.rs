@@ -0,0 +1,435 @@
+//! The gas of a dry run, split by the kind of cost.
+//!
+//! `pallet-contracts` only reports the total gas of a call. The share of a category of host
+//! functions is measured by running the call again with the costs of that category set to zero in
+//! the `Schedule`, see [`ProfiledSchedule`]. The gas charged by the chain extension is measured
+//! directly, as the chain extension reports the gas left when entering and leaving a function.
+//!
+//! Wasm instructions can not be made free, `pallet-contracts` converts gas to fuel with their
+//! weight. Their share is what is left when the costs of all host functions are set to zero, less
+//! the gas of the chain extension.
+//!
+//! Every run is rolled back, so all runs see the same state. A contract whose control flow depends
+//! on the gas left may take different paths in different runs.
+
+use codec::{Decode, Encode};
+use core::marker::PhantomData;
+use frame_support::{
+	storage::{with_transaction_unchecked, TransactionOutcome},
+	traits::{fungible::Inspect, Get},
+	weights::Weight,
+};
+use pallet_contracts::{CollectEvents, DebugInfo, Determinism, HostFnWeights, Schedule};
+use scale_info::TypeInfo;
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+use sp_runtime::{traits::TrailingZeroInput, DispatchError, RuntimeDebug};
+use sp_std::vec::Vec;
+
+type BalanceOf<T> = <<T as pallet_contracts::Config>::Currency as Inspect<
+	<T as frame_system::Config>::AccountId,
+>>::Balance;
+
+/// A category of host function costs in the `Schedule`.
+#[derive(Clone, Copy, Encode, Decode, PartialEq, Eq, RuntimeDebug, TypeInfo)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub enum CostCategory {
+	/// Executing Wasm instructions, with the costs of a call that no host function charges, e.g.
+	/// loading the code.
+	Instructions,
+	/// Reading and writing contract storage.
+	Storage,
+	/// The hash functions.
+	Hashing,
+	/// Signature verification and public key recovery.
+	Crypto,
+	/// Calling and instantiating contracts and transferring balance, without the costs of the
+	/// callee.
+	Calls,
+	/// Emitting events.
+	Events,
+}
+
+impl CostCategory {
+	/// All categories, in the order of [`CallProfile::categories`].
+	pub const ALL: [Self; 6] =
+		[Self::Instructions, Self::Storage, Self::Hashing, Self::Crypto, Self::Calls, Self::Events];
+
+	/// The name of the category in the folded stack format.
+	pub fn name(self) -> &'static str {
+		match self {
+			Self::Instructions => "instructions",
+			Self::Storage => "storage",
+			Self::Hashing => "hashing",
+			Self::Crypto => "crypto",
+			Self::Calls => "calls",
+			Self::Events => "events",
+		}
+	}
+
+	/// The weights of the host functions of this category, none for [`Self::Instructions`].
+	fn host_fn_weights<T: pallet_contracts::Config>(
+		self,
+		w: &mut HostFnWeights<T>,
+	) -> Vec<&mut Weight> {
+		match self {
+			Self::Instructions => Vec::new(),
+			Self::Storage => Vec::from([
+				&mut w.set_storage,
+				&mut w.set_storage_per_new_byte,
+				&mut w.set_storage_per_old_byte,
+				&mut w.clear_storage,
+				&mut w.clear_storage_per_byte,
+				&mut w.contains_storage,
+				&mut w.contains_storage_per_byte,
+				&mut w.get_storage,
+				&mut w.get_storage_per_byte,
+				&mut w.take_storage,
+				&mut w.take_storage_per_byte,
+			]),
+			Self::Hashing => Vec::from([
+				&mut w.hash_sha2_256,
+				&mut w.hash_sha2_256_per_byte,
+				&mut w.hash_keccak_256,
+				&mut w.hash_keccak_256_per_byte,
+				&mut w.hash_blake2_256,
+				&mut w.hash_blake2_256_per_byte,
+				&mut w.hash_blake2_128,
+				&mut w.hash_blake2_128_per_byte,
+			]),
+			Self::Crypto => Vec::from([
+				&mut w.ecdsa_recover,
+				&mut w.ecdsa_to_eth_address,
+				&mut w.sr25519_verify,
+				&mut w.sr25519_verify_per_byte,
+			]),
+			Self::Calls => Vec::from([
+				&mut w.call,
+				&mut w.delegate_call,
+				&mut w.call_transfer_surcharge,
+				&mut w.call_per_cloned_byte,
+				&mut w.instantiate,
+				&mut w.instantiate_transfer_surcharge,
+				&mut w.instantiate_per_input_byte,
+				&mut w.instantiate_per_salt_byte,
+				&mut w.transfer,
+			]),
+			Self::Events => Vec::from([
+				&mut w.deposit_event,
+				&mut w.deposit_event_per_topic,
+				&mut w.deposit_event_per_byte,
+			]),
+		}
+	}
+}
+
+/// The costs set to zero in the `Schedule` while profiling.
+#[derive(Clone, Copy)]
+enum Zeroed {
+	/// The host functions of a category.
+	Category(CostCategory),
+	/// All host functions.
+	HostFns,
+}
+
+impl Zeroed {
+	fn apply<T: pallet_contracts::Config>(self, schedule: &mut Schedule<T>) {
+		match self {
+			Self::Category(category) =>
+				for weight in category.host_fn_weights(&mut schedule.host_fn_weights) {
+					*weight = Weight::zero();
+				},
+			Self::HostFns =>
+				schedule.host_fn_weights = HostFnWeights::decode(&mut TrailingZeroInput::zeroes())
+					.expect("all fields are weights, which decode from zeroes; qed"),
+		}
+	}
+}
+
+/// The gas charged by a chain extension function.
+#[derive(Clone, Encode, Decode, PartialEq, Eq, RuntimeDebug, TypeInfo)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct ChainExtensionCost {
+	pub func_id: u16,
+	/// How often the function was called.
+	pub calls: u32,
+	pub weight: Weight,
+}
+
+/// The gas of a dry run, split by the kind of cost.
+#[derive(Clone, Encode, Decode, PartialEq, Eq, RuntimeDebug, TypeInfo)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct CallProfile {
+	/// The gas consumed by the dry run.
+	pub gas_consumed: Weight,
+	/// The gas of each category of host function costs.
+	pub categories: Vec<(CostCategory, Weight)>,
+	/// The gas charged by the chain extension, by function.
+	pub chain_extension: Vec<ChainExtensionCost>,
+	/// The gas of the host functions in no category, e.g. reading the input.
+	pub other: Weight,
+	/// The error the dry run failed with.
+	pub error: Option<DispatchError>,
+}
+
+#[cfg(feature = "std")]
+impl CallProfile {
+	/// The `ref_time` of the profile in the folded stack format of `flamegraph.pl` and `inferno`,
+	/// with `root` as outermost frame.
+	pub fn folded(&self, root: &str) -> String {
+		let categories = self
+			.categories
+			.iter()
+			.map(|(category, weight)| (category.name().to_string(), weight))
+			.chain(self.chain_extension.iter().map(|cost| {
+				(format!("chain_extension;func_id_{:#x}", cost.func_id), &cost.weight)
+			}))
+			.chain([("other".to_string(), &self.other)]);
+
+		categories
+			.filter(|(_, weight)| weight.ref_time() > 0)
+			.map(|(name, weight)| format!("{root};{name} {}\n", weight.ref_time()))
+			.collect()
+	}
+}
+
+/// The chain extension calls of the dry run being profiled.
+#[derive(Default)]
+struct ChainExtensionCalls {
+	/// The function being executed and the gas left when it was entered.
+	open: Option<(u16, Weight)>,
+	costs: Vec<ChainExtensionCost>,
+}
+
+environmental::environmental!(chain_extension_calls: ChainExtensionCalls);
+environmental::environmental!(zeroed: Zeroed);
+
+/// To be called by the chain extension when a function is entered, with the gas left.
+pub fn chain_extension_entered(func_id: u16, gas_left: Weight) {
+	chain_extension_calls::with(|calls| calls.open = Some((func_id, gas_left)));
+}
+
+/// To be called by the chain extension when a function returns to the contract, with the gas
+/// left. Functions that trap the contract do not need to call it.
+pub fn chain_extension_returned(gas_left: Weight) {
+	chain_extension_calls::with(|calls| {
+		let Some((func_id, entered)) = calls.open.take() else { return };
+		let weight = entered.saturating_sub(gas_left);
+		match calls.costs.iter_mut().find(|cost| cost.func_id == func_id) {
+			Some(cost) => {
+				cost.calls += 1;
+				cost.weight.saturating_accrue(weight);
+			},
+			None => calls.costs.push(ChainExtensionCost { func_id, calls: 1, weight }),
+		}
+	});
+}
+
+/// The `Schedule` of `pallet-contracts`: `S`, with the host function costs being profiled set to
+/// zero.
+pub struct ProfiledSchedule<S>(PhantomData<S>);
+
+impl<T, S> Get<Schedule<T>> for ProfiledSchedule<S>
+where
+	T: pallet_contracts::Config,
+	S: Get<Schedule<T>>,
+{
+	fn get() -> Schedule<T> {
+		let mut schedule = S::get();
+		if let Some(zeroed) = zeroed::with(|zeroed| *zeroed) {
+			zeroed.apply(&mut schedule);
+		}
+		schedule
+	}
+}
+
+/// Dry run a call like `ContractsApi::call` and split the gas it consumed by the kind of cost.
+///
+/// The runtime has to use [`ProfiledSchedule`] as `Schedule`.
+pub fn profile_call<T: pallet_contracts::Config>(
+	origin: T::AccountId,
+	dest: T::AccountId,
+	value: BalanceOf<T>,
+	gas_limit: Weight,
+	storage_deposit_limit: Option<BalanceOf<T>>,
+	input_data: Vec<u8>,
+) -> CallProfile {
+	let dry_run = || {
+		with_transaction_unchecked(|| {
+			TransactionOutcome::Rollback(pallet_contracts::Pallet::<T>::bare_call(
+				origin.clone(),
+				dest.clone(),
+				value,
+				gas_limit,
+				storage_deposit_limit,
+				input_data.clone(),
+				DebugInfo::Skip,
+				CollectEvents::Skip,
+				Determinism::Enforced,
+			))
+		})
+	};
+
+	let mut calls = ChainExtensionCalls::default();
+	let result = chain_extension_calls::using(&mut calls, &dry_run);
+	let gas_consumed = result.gas_consumed;
+
+	let without = |mut zeroed: Zeroed| zeroed::using(&mut zeroed, &dry_run).gas_consumed;
+	let without_host_fns = without(Zeroed::HostFns);
+	let chain_extension = calls
+		.costs
+		.iter()
+		.fold(Weight::zero(), |total, cost| total.saturating_add(cost.weight));
+
+	let mut other = gas_consumed.saturating_sub(without_host_fns);
+	let categories: Vec<_> = CostCategory::ALL
+		.into_iter()
+		.map(|category| {
+			let weight = if category == CostCategory::Instructions {
+				without_host_fns.saturating_sub(chain_extension)
+			} else {
+				let weight = gas_consumed.saturating_sub(without(Zeroed::Category(category)));
+				other.saturating_reduce(weight);
+				weight
+			};
+			(category, weight)
+		})
+		.collect();
+
+	CallProfile {
+		gas_consumed,
+		categories,
+		chain_extension: calls.costs,
+		other,
+		error: result.result.err(),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::mock::{instantiate, new_test_ext, Contracts, Test, ALICE, GAS_LIMIT};
+
+	/// Runs a loop, stores 32 bytes, hashes them and emits the hash.
+	const CONTRACT: &str = r#"
+		(module
+			(import "seal0" "seal_set_storage" (func $seal_set_storage (param i32 i32 i32)))
+			(import "seal0" "seal_hash_keccak_256" (func $seal_hash_keccak_256 (param i32 i32 i32)))
+			(import "seal0" "seal_deposit_event" (func $seal_deposit_event (param i32 i32 i32 i32)))
+			(import "env" "memory" (memory 1 1))
+
+			(func (export "deploy"))
+
+			(func (export "call")
+				(local $i i32)
+				(local.set $i (i32.const 1000))
+				(loop $loop
+					(local.set $i (i32.sub (local.get $i) (i32.const 1)))
+					(br_if $loop (local.get $i))
+				)
+				;; the key at [0, 32), the value at [32, 64), the hash at [64, 96)
+				(call $seal_set_storage (i32.const 0) (i32.const 32) (i32.const 32))
+				(call $seal_hash_keccak_256 (i32.const 32) (i32.const 32) (i32.const 64))
+				(call $seal_deposit_event (i32.const 0) (i32.const 0) (i32.const 64) (i32.const 32))
+			)
+		)
+	"#;
+
+	#[test]
+	fn profile_call_splits_the_gas() {
+		new_test_ext().execute_with(|| {
+			let contract = instantiate(CONTRACT, vec![]);
+			let profile = profile_call::<Test>(ALICE, contract.clone(), 0, GAS_LIMIT, None, vec![]);
+			assert_eq!(profile.error, None);
+
+			// Profiling does not change the call, all runs were rolled back.
+			let call = Contracts::bare_call(
+				ALICE,
+				contract,
+				0,
+				GAS_LIMIT,
+				None,
+				vec![],
+				DebugInfo::Skip,
+				CollectEvents::Skip,
+				Determinism::Enforced,
+			);
+			assert!(call.result.is_ok());
+			assert_eq!(profile.gas_consumed, call.gas_consumed);
+
+			let weight = |category| {
+				profile.categories.iter().find(|(c, _)| *c == category).unwrap().1.ref_time()
+			};
+			assert!(weight(CostCategory::Instructions) > 0);
+			assert!(weight(CostCategory::Storage) > 0);
+			assert!(weight(CostCategory::Hashing) > 0);
+			assert!(weight(CostCategory::Events) > 0);
+			assert_eq!(weight(CostCategory::Crypto), 0);
+			assert_eq!(weight(CostCategory::Calls), 0);
+			assert!(profile.chain_extension.is_empty());
+
+			let total = profile
+				.categories
+				.iter()
+				.fold(profile.other, |total, (_, weight)| total.saturating_add(*weight));
+			assert_eq!(total, profile.gas_consumed);
+		});
+	}
+
+	#[test]
+	fn zeroed_host_fns_keep_the_instruction_weights() {
+		let mut schedule = Schedule::<Test>::default();
+		let instruction_weights = schedule.instruction_weights.clone();
+		Zeroed::HostFns.apply(&mut schedule);
+		assert_eq!(schedule.host_fn_weights.set_storage, Weight::zero());
+		assert_eq!(schedule.host_fn_weights.call_chain_extension, Weight::zero());
+		assert_eq!(schedule.instruction_weights, instruction_weights);
+
+		let mut schedule = Schedule::<Test>::default();
+		Zeroed::Category(CostCategory::Instructions).apply(&mut schedule);
+		assert!(schedule == Schedule::<Test>::default());
+	}
+
+	#[test]
+	fn chain_extension_costs_are_summed_by_func_id() {
+		let mut calls = ChainExtensionCalls::default();
+		chain_extension_calls::using(&mut calls, || {
+			chain_extension_entered(6, Weight::from_parts(100, 10));
+			chain_extension_returned(Weight::from_parts(90, 10));
+			// A function that trapped is not counted.
+			chain_extension_entered(8, Weight::from_parts(90, 10));
+			chain_extension_entered(6, Weight::from_parts(50, 10));
+			chain_extension_returned(Weight::from_parts(45, 8));
+			chain_extension_returned(Weight::from_parts(0, 0));
+		});
+		assert_eq!(
+			calls.costs,
+			vec![ChainExtensionCost { func_id: 6, calls: 2, weight: Weight::from_parts(15, 2) }]
+		);
+	}
+
+	#[test]
+	fn folded_skips_empty_frames() {
+		let profile = CallProfile {
+			gas_consumed: Weight::from_parts(60, 0),
+			categories: vec![
+				(CostCategory::Instructions, Weight::from_parts(30, 0)),
+				(CostCategory::Hashing, Weight::zero()),
+			],
+			chain_extension: vec![ChainExtensionCost {
+				func_id: 0x600,
+				calls: 1,
+				weight: Weight::from_parts(20, 0),
+			}],
+			other: Weight::from_parts(10, 0),
+			error: None,
+		};
+		assert_eq!(
+			profile.folded("call"),
+			"call;instructions 30\ncall;chain_extension;func_id_0x600 20\ncall;other 10\n"
+		);
+	}
+}
@@ -0,0 +1,591 @@
+//! The call tree of a dry run, recorded through the `Debug` hook of `pallet-contracts`.
+//!
+//! The hook is told the called contract, the entry point, the input and the output of every
+//! frame. The caller of a frame is the frame it was called from, or the origin of the dry run.
+//!
+//! Value, gas and storage deposit are not passed to the hook. The value of a frame is what the
+//! free balance of its caller went down by since the caller last ran, so a transfer the caller
+//! made right before the call is counted as well. The gas and storage deposit of a frame are
+//! measured by running the call again, with the results of the frame and all frames after it
+//! replayed from the recording, see [`CallInterceptor`]. Without the frame, the dry run consumes
+//! that much less. Every run is rolled back, so all runs see the same state. A frame that changes
+//! the storage its caller reads afterwards, e.g. by calling back into it, also shifts the numbers
+//! of its caller.
+
+use codec::{Decode, Encode};
+use frame_support::{
+	storage::{with_transaction_unchecked, TransactionOutcome},
+	traits::fungible::Inspect,
+	weights::Weight,
+};
+use pallet_contracts::{
+	debug::{self, CallInterceptor, ExecResult, ExecReturnValue, ExportedFunction, Tracing},
+	CollectEvents, DebugInfo, Determinism, StorageDeposit,
+};
+use scale_info::{
+	prelude::{format, string::String},
+	TypeInfo,
+};
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+use sp_runtime::{
+	traits::{AtLeast32BitUnsigned, UniqueSaturatedFrom, UniqueSaturatedInto, Zero},
+	DispatchError, RuntimeDebug,
+};
+use sp_std::vec::Vec;
+
+type BalanceOf<T> = <<T as pallet_contracts::Config>::Currency as Inspect<
+	<T as frame_system::Config>::AccountId,
+>>::Balance;
+
+/// The selector of Solidity's `Error(string)`.
+const ERROR_SELECTOR: [u8; 4] = [0x08, 0xc3, 0x79, 0xa0];
+/// The selector of Solidity's `Panic(uint256)`.
+const PANIC_SELECTOR: [u8; 4] = [0x4e, 0x48, 0x7b, 0x71];
+
+/// The exported function of a contract a frame executes.
+#[derive(Clone, Copy, Encode, Decode, PartialEq, Eq, RuntimeDebug, TypeInfo)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub enum EntryPoint {
+	Call,
+	Constructor,
+}
+
+/// How a frame ended.
+#[derive(Clone, Copy, Encode, Decode, PartialEq, Eq, RuntimeDebug, TypeInfo)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub enum CallOutcome {
+	/// The contract returned successfully.
+	Returned,
+	/// The contract returned with the revert flag, its changes were rolled back.
+	Reverted,
+	/// The contract trapped, e.g. because it ran out of gas, and produced no output.
+	Trapped,
+}
+
+/// The storage deposit charged or refunded by a call.
+#[derive(Clone, Encode, Decode, PartialEq, Eq, RuntimeDebug, TypeInfo)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub enum Deposit<Balance> {
+	Charge(Balance),
+	Refund(Balance),
+}
+
+impl<Balance> From<StorageDeposit<Balance>> for Deposit<Balance> {
+	fn from(deposit: StorageDeposit<Balance>) -> Self {
+		match deposit {
+			StorageDeposit::Charge(amount) => Self::Charge(amount),
+			StorageDeposit::Refund(amount) => Self::Refund(amount),
+		}
+	}
+}
+
+/// A contract call and the calls it made in turn.
+#[derive(Clone, Encode, Decode, PartialEq, Eq, RuntimeDebug, TypeInfo)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct CallTrace<AccountId, Balance> {
+	/// The origin of the dry run for the outermost call, the calling contract otherwise.
+	pub caller: AccountId,
+	/// The called contract.
+	pub callee: AccountId,
+	/// The exported function that was executed.
+	pub entry_point: EntryPoint,
+	/// The first four bytes of the input, if there are that many.
+	pub selector: Option<[u8; 4]>,
+	#[cfg_attr(feature = "std", serde(with = "sp_core::bytes"))]
+	pub input: Vec<u8>,
+	#[cfg_attr(feature = "std", serde(with = "sp_core::bytes"))]
+	pub output: Vec<u8>,
+	pub outcome: CallOutcome,
+	/// The message of an `Error(string)` or the code of a `Panic(uint256)` a call reverted with.
+	pub revert_reason: Option<String>,
+	/// The value transferred to the callee.
+	pub value: Balance,
+	/// The gas consumed by the code of the callee, including the calls it made.
+	pub gas_consumed: Weight,
+	/// The storage deposit of the callee, including the calls it made.
+	pub storage_deposit: Deposit<Balance>,
+	/// The calls made by this call, in order.
+	pub calls: Vec<CallTrace<AccountId, Balance>>,
+}
+
+/// The result of [`trace_call`]: the tree of calls and the totals of the dry run.
+#[derive(Clone, Encode, Decode, PartialEq, Eq, RuntimeDebug, TypeInfo)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct TracedCall<AccountId, Balance> {
+	/// The outermost call.
+	pub trace: CallTrace<AccountId, Balance>,
+	/// The error the dry run failed with.
+	pub error: Option<DispatchError>,
+	/// The gas consumed by the dry run, including all nested calls.
+	pub gas_consumed: Weight,
+	/// The gas limit the dry run needs to succeed.
+	pub gas_required: Weight,
+	/// The storage deposit of the dry run, including all nested calls.
+	pub storage_deposit: Deposit<Balance>,
+}
+
+/// Whether a call reverted and its output, `None` if it trapped.
+type Output = Option<(bool, Vec<u8>)>;
+
+/// The gas consumed and the storage deposit of a run of [`trace_call`].
+type Run<Balance> = (Weight, StorageDeposit<Balance>);
+
+/// A frame as recorded by [`CallTracer`], with the callee still encoded.
+struct Frame {
+	callee: Vec<u8>,
+	entry_point: EntryPoint,
+	input: Vec<u8>,
+	output: Output,
+	value: u128,
+	/// The free balance of the callee when it last ran: when it was called, or when a call it
+	/// made returned.
+	balance: u128,
+	/// The position of the frame among all frames, in the order they were entered.
+	index: usize,
+	/// The position of the first frame after this one and the frames it called.
+	end: usize,
+	calls: Vec<Frame>,
+}
+
+impl Frame {
+	/// The trace of the frame, with its gas and storage deposit from the `runs` of
+	/// [`trace_call`], by the number of frames replayed.
+	fn into_trace<AccountId, Balance>(
+		self,
+		caller: AccountId,
+		runs: &[Run<Balance>],
+	) -> CallTrace<AccountId, Balance>
+	where
+		AccountId: Decode + Clone,
+		Balance: AtLeast32BitUnsigned + Copy,
+	{
+		let callee = AccountId::decode(&mut &self.callee[..])
+			.expect("encoded from the same `AccountId` type by `CallTracer`; qed");
+		let (outcome, output) = match self.output {
+			Some((false, output)) => (CallOutcome::Returned, output),
+			Some((true, output)) => (CallOutcome::Reverted, output),
+			None => (CallOutcome::Trapped, Vec::new()),
+		};
+		let revert_reason =
+			if outcome == CallOutcome::Reverted { revert_reason(&output) } else { None };
+		let (gas_consumed, storage_deposit) = match (runs.get(self.index), runs.get(self.end)) {
+			(Some((gas_before, deposit_before)), Some((gas_after, deposit_after))) => (
+				gas_after.saturating_sub(*gas_before),
+				deposit_after.saturating_sub(deposit_before).into(),
+			),
+			_ => (Weight::zero(), Deposit::Charge(Balance::zero())),
+		};
+		let calls = self
+			.calls
+			.into_iter()
+			.map(|frame| frame.into_trace(callee.clone(), runs))
+			.collect();
+
+		CallTrace {
+			caller,
+			callee,
+			entry_point: self.entry_point,
+			selector: self.input.get(..4).and_then(|selector| selector.try_into().ok()),
+			input: self.input,
+			output,
+			outcome,
+			revert_reason,
+			value: Balance::unique_saturated_from(self.value),
+			gas_consumed,
+			storage_deposit,
+			calls,
+		}
+	}
+}
+
+/// The frames of the dry run being traced.
+struct TraceStack {
+	/// The origin of the dry run, encoded.
+	origin: Vec<u8>,
+	/// The free balance of the origin before the dry run.
+	origin_balance: u128,
+	/// The free balance of an encoded account.
+	balance_of: fn(&[u8]) -> u128,
+	/// The frames that did not return yet, innermost last.
+	open: Vec<Frame>,
+	/// The outermost frame, once it returned.
+	root: Option<Frame>,
+	/// The outputs of all frames, in the order they were entered.
+	outputs: Vec<Output>,
+}
+
+impl TraceStack {
+	fn new(origin: Vec<u8>, balance_of: fn(&[u8]) -> u128) -> Self {
+		let origin_balance = balance_of(&origin);
+		Self {
+			origin,
+			origin_balance,
+			balance_of,
+			open: Vec::new(),
+			root: None,
+			outputs: Vec::new(),
+		}
+	}
+
+	/// The account of the frame being executed, or the origin before the dry run started, and
+	/// its free balance when it last ran.
+	fn caller(&mut self) -> (&[u8], &mut u128) {
+		match self.open.last_mut() {
+			Some(frame) => (&frame.callee, &mut frame.balance),
+			None => (&self.origin, &mut self.origin_balance),
+		}
+	}
+
+	fn enter(&mut self, callee: Vec<u8>, entry_point: EntryPoint, input: &[u8]) {
+		let balance_of = self.balance_of;
+		let (caller, last_balance) = self.caller();
+		let value = last_balance.saturating_sub(balance_of(caller));
+		let balance = balance_of(&callee);
+		let index = self.outputs.len();
+		self.outputs.push(None);
+		self.open.push(Frame {
+			callee,
+			entry_point,
+			input: input.to_vec(),
+			output: None,
+			value,
+			balance,
+			index,
+			end: index,
+			calls: Vec::new(),
+		});
+	}
+
+	fn exit(&mut self, output: Output) {
+		let Some(mut frame) = self.open.pop() else { return };
+		frame.end = self.outputs.len();
+		self.outputs[frame.index] = output.clone();
+		// A call that reverted or trapped is rolled back, with its value, once it returned.
+		if matches!(output, Some((false, _))) {
+			let balance_of = self.balance_of;
+			let (caller, last_balance) = self.caller();
+			*last_balance = balance_of(caller);
+		}
+		frame.output = output;
+		match self.open.last_mut() {
+			Some(parent) => parent.calls.push(frame),
+			None => self.root = Some(frame),
+		}
+	}
+}
+
+environmental::environmental!(trace_stack: TraceStack);
+
+/// The outputs recorded by [`trace_call`], for the frames of a run to return without executing.
+struct Replay {
+	outputs: Vec<Output>,
+	/// The first frame to replay, it and all frames after it return their recorded output.
+	from: usize,
+	/// The position of the next frame.
+	next: usize,
+}
+
+impl Replay {
+	fn intercept(&mut self) -> Option<ExecResult> {
+		let index = self.next;
+		self.next += 1;
+		if index < self.from {
+			return None
+		}
+		// `ExecError` is not exported by `pallet-contracts`, so the result is decoded. A trap is
+		// an error with `ErrorOrigin::Callee`, which the caller sees as `CalleeTrapped`.
+		let result = match self.outputs.get(index)? {
+			Some((reverted, data)) => Ok::<_, ()>((u32::from(*reverted), data)).encode(),
+			None => Err::<(), _>((DispatchError::Other("replayed trap"), 1u8)).encode(),
+		};
+		ExecResult::decode(&mut &result[..]).ok()
+	}
+}
+
+environmental::environmental!(replay: Replay);
+
+/// The free balance of an account encoded by [`CallTracer`].
+fn balance_of<T: pallet_contracts::Config>(account: &[u8]) -> u128 {
+	let account = T::AccountId::decode(&mut &account[..])
+		.expect("encoded from the same `AccountId` type by `CallTracer`; qed");
+	T::Currency::balance(&account).unique_saturated_into()
+}
+
+/// The `Debug` hook of `pallet-contracts` that records and replays the calls of [`trace_call`],
+/// and does nothing for any other execution.
+pub struct CallTracer;
+
+impl<T: pallet_contracts::Config> Tracing<T> for CallTracer {
+	type CallSpan = TraceSpan;
+
+	fn new_call_span(
+		contract_account: &T::AccountId,
+		entry_point: ExportedFunction,
+		input_data: &[u8],
+	) -> TraceSpan {
+		let entry_point = match entry_point {
+			ExportedFunction::Call => EntryPoint::Call,
+			ExportedFunction::Constructor => EntryPoint::Constructor,
+		};
+		let open = trace_stack::with(|stack| {
+			stack.enter(contract_account.encode(), entry_point, input_data)
+		})
+		.is_some();
+		TraceSpan { open }
+	}
+}
+
+impl<T: pallet_contracts::Config> CallInterceptor<T> for CallTracer {
+	fn intercept_call(
+		_contract_address: &T::AccountId,
+		_entry_point: &ExportedFunction,
+		_input_data: &[u8],
+	) -> Option<ExecResult> {
+		replay::with(|replay| replay.intercept()).flatten()
+	}
+}
+
+/// A frame of [`CallTracer`]. It is closed when the call returns, or when it is dropped without
+/// returning because the call trapped.
+pub struct TraceSpan {
+	open: bool,
+}
+
+impl debug::CallSpan for TraceSpan {
+	fn after_call(mut self, output: &ExecReturnValue) {
+		if core::mem::take(&mut self.open) {
+			trace_stack::with(|stack| stack.exit(Some((output.did_revert(), output.data.clone()))));
+		}
+	}
+}
+
+impl Drop for TraceSpan {
+	fn drop(&mut self) {
+		if self.open {
+			trace_stack::with(|stack| stack.exit(None));
+		}
+	}
+}
+
+/// Dry run a call like `ContractsApi::call` and return the tree of all contract calls it made.
+///
+/// The call is run once more for every frame, to measure its gas and storage deposit. The runtime
+/// has to use [`CallTracer`] as `Debug`, otherwise only the outermost call is reported.
+pub fn trace_call<T: pallet_contracts::Config>(
+	origin: T::AccountId,
+	dest: T::AccountId,
+	value: BalanceOf<T>,
+	gas_limit: Weight,
+	storage_deposit_limit: Option<BalanceOf<T>>,
+	input_data: Vec<u8>,
+) -> TracedCall<T::AccountId, BalanceOf<T>> {
+	let dry_run = || {
+		with_transaction_unchecked(|| {
+			TransactionOutcome::Rollback(pallet_contracts::Pallet::<T>::bare_call(
+				origin.clone(),
+				dest.clone(),
+				value,
+				gas_limit,
+				storage_deposit_limit,
+				input_data.clone(),
+				DebugInfo::Skip,
+				CollectEvents::Skip,
+				Determinism::Enforced,
+			))
+		})
+	};
+
+	let mut stack = TraceStack::new(origin.encode(), balance_of::<T>);
+	let result = trace_stack::using(&mut stack, &dry_run);
+
+	// The run with the frames from `from` on replayed, for every frame, then the recorded run,
+	// which replays none.
+	let mut replay = Replay { outputs: core::mem::take(&mut stack.outputs), from: 0, next: 0 };
+	let mut runs: Vec<Run<BalanceOf<T>>> = (0..replay.outputs.len())
+		.map(|from| {
+			replay.from = from;
+			replay.next = 0;
+			let result = replay::using(&mut replay, &dry_run);
+			(result.gas_consumed, result.storage_deposit)
+		})
+		.collect();
+	runs.push((result.gas_consumed, result.storage_deposit.clone()));
+
+	let trace = match stack.root.take() {
+		Some(root) => root.into_trace(origin, &runs),
+		// The call failed before the contract was executed, e.g. because `dest` is no contract, or
+		// the runtime does not record calls.
+		None => Frame {
+			callee: dest.encode(),
+			entry_point: EntryPoint::Call,
+			input: input_data,
+			output: result
+				.result
+				.as_ref()
+				.ok()
+				.map(|output| (output.did_revert(), output.data.clone())),
+			value: value.unique_saturated_into(),
+			balance: 0,
+			index: 0,
+			end: 0,
+			calls: Vec::new(),
+		}
+		.into_trace(origin, &runs),
+	};
+	TracedCall {
+		trace,
+		error: result.result.err(),
+		gas_consumed: result.gas_consumed,
+		gas_required: result.gas_required,
+		storage_deposit: result.storage_deposit.into(),
+	}
+}
+
+/// The reason encoded in the output of a reverted Solang contract: the selector of `Error(string)`
+/// followed by the SCALE encoded message, or of `Panic(uint256)` followed by the code.
+fn revert_reason(output: &[u8]) -> Option<String> {
+	let (selector, mut data) = (output.get(..4)?, output.get(4..)?);
+	if selector == ERROR_SELECTOR {
+		return String::decode(&mut data).ok()
+	}
+	if selector == PANIC_SELECTOR {
+		let code = <[u8; 32]>::decode(&mut data).ok()?;
+		return Some(format!("Panic(0x{:02x})", code[0]))
+	}
+	None
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::mock::{instantiate, new_test_ext, Test, ALICE, GAS_LIMIT};
+
+	/// Calls the contract whose address is the input with 1000 as value and `01020304` as input.
+	const CALLER: &str = r#"
+		(module
+			(import "seal0" "seal_input" (func $seal_input (param i32 i32)))
+			(import "seal0" "seal_call"
+				(func $seal_call (param i32 i32 i64 i32 i32 i32 i32 i32 i32) (result i32)))
+			(import "env" "memory" (memory 1 1))
+
+			;; the callee at [0, 32), its length at [32, 36), the value at [36, 44) and the input
+			;; at [44, 48)
+			(data (i32.const 32) "\20")
+			(data (i32.const 36) "\e8\03")
+			(data (i32.const 44) "\01\02\03\04")
+
+			(func (export "deploy"))
+
+			(func (export "call")
+				(call $seal_input (i32.const 0) (i32.const 32))
+				(drop (call $seal_call
+					(i32.const 0) (i32.const 32)
+					(i64.const 0)
+					(i32.const 36) (i32.const 8)
+					(i32.const 44) (i32.const 4)
+					(i32.const 4294967295) (i32.const 0)
+				))
+			)
+		)
+	"#;
+
+	/// Stores four bytes.
+	const CALLEE: &str = r#"
+		(module
+			(import "seal0" "seal_set_storage" (func $seal_set_storage (param i32 i32 i32)))
+			(import "env" "memory" (memory 1 1))
+
+			(func (export "deploy"))
+
+			(func (export "call")
+				(call $seal_set_storage (i32.const 0) (i32.const 0) (i32.const 4))
+			)
+		)
+	"#;
+
+	#[test]
+	fn nested_calls_have_value_gas_and_deposit() {
+		new_test_ext().execute_with(|| {
+			let caller = instantiate(CALLER, vec![]);
+			let callee = instantiate(CALLEE, vec![]);
+			let traced = trace_call::<Test>(
+				ALICE,
+				caller.clone(),
+				1_000_000,
+				GAS_LIMIT,
+				None,
+				callee.encode(),
+			);
+			assert_eq!(traced.error, None);
+
+			let root = traced.trace;
+			assert_eq!((root.caller, root.callee.clone()), (ALICE, caller.clone()));
+			assert_eq!(root.value, 1_000_000);
+			assert_eq!(root.storage_deposit, traced.storage_deposit);
+			assert_eq!(root.calls.len(), 1);
+
+			let child = &root.calls[0];
+			assert_eq!((&child.caller, &child.callee), (&caller, &callee));
+			assert_eq!(child.entry_point, EntryPoint::Call);
+			assert_eq!(child.selector, Some([1, 2, 3, 4]));
+			assert_eq!(child.outcome, CallOutcome::Returned);
+			assert_eq!(child.value, 1000);
+			assert!(child.gas_consumed.ref_time() > 0);
+			assert!(child.gas_consumed.ref_time() < root.gas_consumed.ref_time());
+			assert!(root.gas_consumed.ref_time() < traced.gas_consumed.ref_time());
+			// Only the callee stores something.
+			assert!(matches!(child.storage_deposit, Deposit::Charge(amount) if amount > 0));
+			assert_eq!(child.storage_deposit, traced.storage_deposit);
+			assert!(child.calls.is_empty());
+		});
+	}
+
+	#[test]
+	fn revert_reasons() {
+		let mut error = ERROR_SELECTOR.to_vec();
+		String::from("insufficient balance").encode_to(&mut error);
+		assert_eq!(revert_reason(&error).as_deref(), Some("insufficient balance"));
+
+		let mut panic = PANIC_SELECTOR.to_vec();
+		panic.extend_from_slice(&{
+			let mut code = [0u8; 32];
+			code[0] = 0x11;
+			code
+		});
+		assert_eq!(revert_reason(&panic).as_deref(), Some("Panic(0x11)"));
+
+		assert_eq!(revert_reason(&[1, 2, 3, 4, 5]), None);
+		assert_eq!(revert_reason(&ERROR_SELECTOR[..3]), None);
+	}
+
+	#[test]
+	fn trapped_frames_are_closed() {
+		let mut stack = TraceStack::new(vec![0], |_| 0);
+		stack.enter(vec![1], EntryPoint::Call, &[0xaa; 4]);
+		stack.enter(vec![2], EntryPoint::Call, &[0xbb; 4]);
+		// The inner call traps, the outer one continues and returns.
+		stack.exit(None);
+		stack.enter(vec![3], EntryPoint::Constructor, &[]);
+		stack.exit(Some((true, vec![9])));
+		stack.exit(Some((false, vec![])));
+
+		let trace: CallTrace<u8, u64> = stack.root.take().unwrap().into_trace(0, &[]);
+		assert_eq!(trace.callee, 1);
+		assert_eq!(trace.selector, Some([0xaa; 4]));
+		assert_eq!(trace.outcome, CallOutcome::Returned);
+		assert_eq!(trace.calls.len(), 2);
+		assert_eq!((trace.calls[0].caller, trace.calls[0].callee), (1, 2));
+		assert_eq!(trace.calls[0].outcome, CallOutcome::Trapped);
+		assert_eq!(trace.calls[1].callee, 3);
+		assert_eq!(trace.calls[1].selector, None);
+		assert_eq!(trace.calls[1].outcome, CallOutcome::Reverted);
+		assert_eq!(trace.calls[1].output, vec![9]);
+		assert!(stack.open.is_empty());
+	}
+}
@@ -0,0 +1,10 @@
+#![no_main]
+
+use contracts_chain_extension::fuzzing;
+use contracts_chain_extension_fuzz::{evm, G1Input};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|points: (G1Input, G1Input)| {
+	let input = [points.0.encode(), points.1.encode()].concat();
+	assert_eq!(fuzzing::bn128_add(&input), evm::add(&input));
+});
@@ -0,0 +1,10 @@
+#![no_main]
+
+use contracts_chain_extension::fuzzing;
+use contracts_chain_extension_fuzz::{evm, G1Input};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|input: (G1Input, [u8; 32])| {
+	let input = [input.0.encode(), input.1.to_vec()].concat();
+	assert_eq!(fuzzing::bn128_mul(&input), evm::mul(&input));
+});
@@ -0,0 +1,17 @@
+#![no_main]
+
+use contracts_chain_extension::fuzzing;
+use contracts_chain_extension_fuzz::{evm, G1Input, G2Input};
+use libfuzzer_sys::fuzz_target;
+
+/// Keeps each run short, the chain extension accepts any number of pairs.
+const MAX_PAIRS: usize = 4;
+
+fuzz_target!(|pairs: Vec<(G1Input, G2Input)>| {
+	let input: Vec<u8> = pairs
+		.iter()
+		.take(MAX_PAIRS)
+		.flat_map(|(a, b)| [a.encode(), b.encode()].concat())
+		.collect();
+	assert_eq!(fuzzing::bn128_pairing(&input), evm::pairing(&input));
+});
@@ -0,0 +1,10 @@
+#![no_main]
+
+use contracts_chain_extension::fuzzing;
+use contracts_chain_extension_fuzz::circomlib;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|input: ([u8; 32], [u8; 32])| {
+	// Arbitrary words must not panic, non-canonical field elements are rejected.
+	assert_eq!(fuzzing::mimc_feistel(input.0, input.1), circomlib::feistel(input.0, input.1));
+});
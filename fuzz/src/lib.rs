@@ -0,0 +1,151 @@
+//! Reference implementations and structured inputs for the chain extension fuzz targets.
+//!
+//! The chain extension takes every field element as a 32 byte little-endian word, the EVM
+//! precompiles used as reference take big-endian words. [`swap_words`] converts between the two.
+
+use ark_bn254::{Fq, Fr, G1Projective, G2Projective};
+use ark_ec::{AffineRepr, CurveGroup, Group};
+use ark_ff::{BigInt, BigInteger, Field, PrimeField};
+use libfuzzer_sys::arbitrary::{self, Arbitrary};
+
+/// Reverse the byte order of every 32 byte word.
+pub fn swap_words(input: &[u8]) -> Vec<u8> {
+	input.chunks(32).flat_map(|word| word.iter().rev().copied()).collect()
+}
+
+fn fq_word(fq: &Fq) -> Vec<u8> {
+	fq.into_bigint().to_bytes_le()
+}
+
+/// A G1 point as passed to the chain extension.
+///
+/// Random bytes are almost never a point on the curve, so most inputs are built from multiples of
+/// the generator instead to exercise the arithmetic and not just the input validation.
+#[derive(Arbitrary, Debug)]
+pub enum G1Input {
+	Raw([u8; 64]),
+	Multiple(u64),
+	NegMultiple(u64),
+}
+
+impl G1Input {
+	pub fn encode(&self) -> Vec<u8> {
+		let point = match self {
+			Self::Raw(raw) => return raw.to_vec(),
+			Self::Multiple(k) => G1Projective::generator() * Fr::from(*k),
+			Self::NegMultiple(k) => -(G1Projective::generator() * Fr::from(*k)),
+		};
+		match point.into_affine().xy() {
+			Some((x, y)) => [fq_word(x), fq_word(y)].concat(),
+			None => vec![0; 64],
+		}
+	}
+}
+
+/// A G2 point as passed to the chain extension, imaginary parts first.
+#[derive(Arbitrary, Debug)]
+pub enum G2Input {
+	Raw([u8; 128]),
+	Multiple(u64),
+}
+
+impl G2Input {
+	pub fn encode(&self) -> Vec<u8> {
+		let point = match self {
+			Self::Raw(raw) => return raw.to_vec(),
+			Self::Multiple(k) => G2Projective::generator() * Fr::from(*k),
+		};
+		match point.into_affine().xy() {
+			Some((x, y)) =>
+				[fq_word(&x.c1), fq_word(&x.c0), fq_word(&y.c1), fq_word(&y.c0)].concat(),
+			None => vec![0; 128],
+		}
+	}
+}
+
+/// The alt_bn128 precompiles of revm.
+pub mod evm {
+	use super::swap_words;
+	use revm_precompile::{Address, Bytes, Precompile, Precompiles};
+
+	fn run(address: u8, input: &[u8]) -> Option<Vec<u8>> {
+		let precompile = Precompiles::istanbul()
+			.get(&Address::with_last_byte(address))
+			.expect("alt_bn128 precompiles exist since Byzantium; qed");
+		let Precompile::Standard(run) = precompile else {
+			unreachable!("alt_bn128 precompiles are standard precompiles; qed")
+		};
+		run(&Bytes::copy_from_slice(&swap_words(input)), u64::MAX)
+			.ok()
+			.map(|(_, output)| output.to_vec())
+	}
+
+	pub fn add(input: &[u8]) -> Option<[u8; 64]> {
+		run(6, input).map(|output| swap_words(&output).try_into().unwrap())
+	}
+
+	pub fn mul(input: &[u8]) -> Option<[u8; 64]> {
+		run(7, input).map(|output| swap_words(&output).try_into().unwrap())
+	}
+
+	pub fn pairing(input: &[u8]) -> Option<bool> {
+		run(8, input).map(|output| output[31] == 1)
+	}
+}
+
+/// A port of circomlib's MiMC sponge Feistel permutation (`mimcsponge.circom`).
+pub mod circomlib {
+	use super::*;
+	use std::sync::OnceLock;
+	use tiny_keccak::{Hasher, Keccak};
+
+	const ROUNDS: usize = 220;
+
+	fn keccak_256(input: &[u8]) -> [u8; 32] {
+		let mut hasher = Keccak::v256();
+		hasher.update(input);
+		let mut output = [0u8; 32];
+		hasher.finalize(&mut output);
+		output
+	}
+
+	/// The round constants, a keccak chain seeded with `"mimcsponge"`. The first and the last
+	/// constant are zero.
+	fn constants() -> &'static [Fr] {
+		static CONSTANTS: OnceLock<Vec<Fr>> = OnceLock::new();
+		CONSTANTS.get_or_init(|| {
+			let mut constants = vec![Fr::from(0u64); ROUNDS];
+			let mut c = keccak_256(b"mimcsponge");
+			for constant in &mut constants[1..ROUNDS - 1] {
+				c = keccak_256(&c);
+				*constant = Fr::from_be_bytes_mod_order(&c);
+			}
+			constants
+		})
+	}
+
+	fn decode(word: [u8; 32]) -> Option<Fr> {
+		let limbs =
+			core::array::from_fn(|i| u64::from_le_bytes(word[i * 8..][..8].try_into().unwrap()));
+		Fr::from_bigint(BigInt::new(limbs))
+	}
+
+	fn encode(fr: Fr) -> [u8; 32] {
+		fr.into_bigint().to_bytes_le().try_into().unwrap()
+	}
+
+	/// `MiMCFeistel(220)` with `k = 0`, or `None` if an input is not a canonical field element.
+	pub fn feistel(x_l: [u8; 32], x_r: [u8; 32]) -> Option<([u8; 32], [u8; 32])> {
+		let (mut x_l, mut x_r) = (decode(x_l)?, decode(x_r)?);
+		for (i, c) in constants().iter().enumerate() {
+			let t = x_l + c;
+			let t5 = t.square().square() * t;
+			if i < ROUNDS - 1 {
+				(x_l, x_r) = (x_r + t5, x_l);
+			} else {
+				x_r += t5;
+			}
+		}
+		Some((encode(x_l), encode(x_r)))
+	}
+}
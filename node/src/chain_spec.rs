@@ -148,6 +148,9 @@ fn testnet_genesis(
 		"polkadotXcm": {
 			"safeXcmVersion": Some(SAFE_XCM_VERSION),
 		},
-		"sudo": { "key": Some(root) }
+		"sudo": { "key": Some(root) },
+		"contractsCallFilter": {
+			"allowedCalls": [("Balances".as_bytes(), "transfer_allow_death".as_bytes())],
+		},
 	})
 }
@@ -23,7 +23,12 @@ where
 	AccountPublic::from(get_from_seed::<TPublic>(seed)).into_account()
 }
 
-pub fn development_config() -> Result<ChainSpec, String> {
+/// The dev chain, with `author` (Alice by default) reported as block author and the randomness
+/// seeded with `random_seed`, if any.
+pub fn development_config(
+	author: Option<AccountId>,
+	random_seed: Option<[u8; 32]>,
+) -> Result<ChainSpec, String> {
 	Ok(ChainSpec::builder(
 		WASM_BINARY.ok_or_else(|| "Development wasm not available".to_string())?,
 		None,
@@ -50,6 +55,9 @@ pub fn development_config() -> Result<ChainSpec, String> {
 			get_account_id_from_seed::<sr25519::Public>("Ferdie"),
 			get_account_id_from_seed::<sr25519::Public>("Ferdie//stash"),
 		],
+		// Block author
+		author.unwrap_or_else(|| get_account_id_from_seed::<sr25519::Public>("Alice")),
+		random_seed,
 		true,
 	))
 	.build())
@@ -59,6 +67,8 @@ pub fn development_config() -> Result<ChainSpec, String> {
 fn testnet_genesis(
 	root_key: AccountId,
 	endowed_accounts: Vec<AccountId>,
+	author: AccountId,
+	random_seed: Option<[u8; 32]>,
 	_enable_println: bool,
 ) -> serde_json::Value {
 	serde_json::json!({
@@ -70,5 +80,15 @@ fn testnet_genesis(
 			// Assign network admin rights.
 			"key": Some(root_key),
 		},
+		"contractsContext": {
+			"devAuthor": Some(author),
+		},
+		"devRandomness": {
+			"seed": random_seed,
+		},
+		"contractsCallFilter": {
+			// The calls contracts may dispatch, sudo can allow more.
+			"allowedCalls": [("Balances".as_bytes(), "transfer_allow_death".as_bytes())],
+		},
 	})
 }
@@ -85,6 +85,30 @@ pub struct Cli {
 	/// The number of seconds to delay before finalizing blocks.
 	#[arg(long, default_value_t = 1)]
 	pub finalize_delay_sec: u8,
+
+	/// The account reported as block author by the dev chain, as SS58 address (Alice by default).
+	///
+	/// Contracts see it as `block.coinbase`. Only applies to new `--dev` chains.
+	#[arg(long, value_name = "SS58")]
+	pub dev_author: Option<contracts_node_runtime::AccountId>,
+
+	/// Seed the randomness of the dev chain, as hex encoded word of up to 32 bytes.
+	///
+	/// Makes the randomness contracts get reproducible across runs. Only applies to new `--dev`
+	/// chains, sudo can change the seed later.
+	#[arg(long, value_name = "HEX", value_parser = parse_random_seed)]
+	pub random_seed: Option<[u8; 32]>,
+}
+
+/// Parse a hex encoded word of up to 32 bytes, right-aligned like a `uint256`.
+fn parse_random_seed(hex: &str) -> Result<[u8; 32], String> {
+	let bytes = sp_core::bytes::from_hex(hex).map_err(|err| format!("invalid hex: {err}"))?;
+	if bytes.len() > 32 {
+		return Err(format!("expected at most 32 bytes, got {}", bytes.len()))
+	}
+	let mut seed = [0u8; 32];
+	seed[32 - bytes.len()..].copy_from_slice(&bytes);
+	Ok(seed)
 }
 
 #[derive(Debug)]
@@ -17,9 +17,14 @@ use crate::{
 	service::{dev, new_partial},
 };
 
-fn load_spec(id: &str) -> std::result::Result<Box<dyn ChainSpec>, String> {
+fn load_spec(
+	id: &str,
+	dev_author: Option<contracts_node_runtime::AccountId>,
+	random_seed: Option<[u8; 32]>,
+) -> std::result::Result<Box<dyn ChainSpec>, String> {
 	Ok(match id {
-		"" | "dev" => Box::new(chain_spec::dev::development_config().unwrap()),
+		"" | "dev" =>
+			Box::new(chain_spec::dev::development_config(dev_author, random_seed).unwrap()),
 		"contracts-parachain-local" => Box::new(chain_spec::local_testnet_config()),
 		path => Box::new(chain_spec::ChainSpec::from_json_file(std::path::PathBuf::from(path))?),
 	})
@@ -51,7 +56,7 @@ impl SubstrateCli for Cli {
 	}
 
 	fn load_spec(&self, id: &str) -> std::result::Result<Box<dyn sc_service::ChainSpec>, String> {
-		load_spec(id)
+		load_spec(id, self.dev_author.clone(), self.random_seed)
 	}
 }
 
@@ -5,9 +5,12 @@
 
 #![warn(missing_docs)]
 
+mod account_mapping;
+mod contracts;
+
 use std::sync::Arc;
 
-use contracts_parachain_runtime::{opaque::Block, AccountId, Balance, Nonce};
+use contracts_parachain_runtime::{opaque::Block, AccountId, Balance, BlockNumber, Hash, Nonce};
 
 pub use sc_rpc::DenyUnsafe;
 use sc_transaction_pool_api::TransactionPool;
@@ -42,8 +45,12 @@ where
 	C::Api: pallet_transaction_payment_rpc::TransactionPaymentRuntimeApi<Block, Balance>,
 	C::Api: substrate_frame_rpc_system::AccountNonceApi<Block, AccountId, Nonce>,
 	C::Api: BlockBuilder<Block>,
+	C::Api: contracts_dev_api::ContractsDevApi<Block, AccountId, Balance, BlockNumber, Hash>,
+	C::Api: pallet_account_mapping::AccountMappingApi<Block>,
 	P: TransactionPool + Sync + Send + 'static,
 {
+	use account_mapping::{AccountMapping, AccountMappingRpcServer};
+	use contracts::{ContractsDev, ContractsDevRpcServer};
 	use pallet_transaction_payment_rpc::{TransactionPayment, TransactionPaymentApiServer};
 	use substrate_frame_rpc_system::{System, SystemApiServer};
 
@@ -51,6 +58,8 @@ where
 	let FullDeps { client, pool, deny_unsafe } = deps;
 
 	module.merge(System::new(client.clone(), pool, deny_unsafe).into_rpc())?;
-	module.merge(TransactionPayment::new(client).into_rpc())?;
+	module.merge(TransactionPayment::new(client.clone()).into_rpc())?;
+	module.merge(ContractsDev::new(client.clone()).into_rpc())?;
+	module.merge(AccountMapping::new(client).into_rpc())?;
 	Ok(module)
 }
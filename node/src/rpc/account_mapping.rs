@@ -0,0 +1,53 @@
+//! The `accountMapping_*` RPC methods, served from the `AccountMappingApi` of the runtime.
+
+use std::{marker::PhantomData, sync::Arc};
+
+use jsonrpsee::{core::RpcResult, proc_macros::rpc};
+use pallet_account_mapping::AccountMappingApi;
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_core::H160;
+use sp_runtime::{traits::Block as BlockT, AccountId32};
+
+use super::contracts::runtime_error;
+
+/// Account mapping RPC methods.
+#[rpc(server)]
+pub trait AccountMappingRpc<BlockHash> {
+	/// The Ethereum address of `account`.
+	#[method(name = "accountMapping_addressOf")]
+	fn address_of(&self, account: AccountId32, at: Option<BlockHash>) -> RpcResult<H160>;
+
+	/// The account of the Ethereum `address`.
+	#[method(name = "accountMapping_accountOf")]
+	fn account_of(&self, address: H160, at: Option<BlockHash>) -> RpcResult<AccountId32>;
+}
+
+/// Serves [`AccountMappingRpcServer`] with the runtime API of `client`.
+pub struct AccountMapping<C, Block> {
+	client: Arc<C>,
+	_marker: PhantomData<Block>,
+}
+
+impl<C, Block> AccountMapping<C, Block> {
+	pub fn new(client: Arc<C>) -> Self {
+		Self { client, _marker: Default::default() }
+	}
+}
+
+impl<C, Block> AccountMappingRpcServer<Block::Hash> for AccountMapping<C, Block>
+where
+	Block: BlockT,
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + Send + Sync + 'static,
+	C::Api: AccountMappingApi<Block>,
+{
+	fn address_of(&self, account: AccountId32, at: Option<Block::Hash>) -> RpcResult<H160> {
+		let at = at.unwrap_or_else(|| self.client.info().best_hash);
+		self.client.runtime_api().address_of(at, account).map_err(runtime_error)
+	}
+
+	fn account_of(&self, address: H160, at: Option<Block::Hash>) -> RpcResult<AccountId32> {
+		let at = at.unwrap_or_else(|| self.client.info().best_hash);
+		self.client.runtime_api().account_of(at, address).map_err(runtime_error)
+	}
+}
@@ -0,0 +1,291 @@
+//! The `contracts_*` RPC methods, served from the `ContractsDevApi` of the runtime.
+
+use std::{marker::PhantomData, sync::Arc};
+
+use codec::Codec;
+use contracts_dev_api::{
+	CallProfile, ContractsDevApi, Deposit, Determinism, GasEstimate, TracedCall, Weight,
+};
+use jsonrpsee::{
+	core::RpcResult,
+	proc_macros::rpc,
+	types::error::{ErrorObject, ErrorObjectOwned},
+};
+use serde::{Deserialize, Serialize};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_core::Bytes;
+use sp_runtime::{
+	traits::{Block as BlockT, NumberFor},
+	DispatchError,
+};
+
+/// The error code of failed runtime API calls.
+const RUNTIME_ERROR: i32 = 1;
+
+/// A contract call to dry run.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[serde(deny_unknown_fields)]
+pub struct CallRequest<AccountId, Balance> {
+	pub origin: AccountId,
+	pub dest: AccountId,
+	pub value: Balance,
+	pub gas_limit: Option<Weight>,
+	pub storage_deposit_limit: Option<Balance>,
+	pub input_data: Bytes,
+}
+
+/// The code of a contract to instantiate.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Code<Hash> {
+	Upload(Bytes),
+	Existing(Hash),
+}
+
+impl<Hash> From<Code<Hash>> for contracts_dev_api::Code<Hash> {
+	fn from(code: Code<Hash>) -> Self {
+		match code {
+			Code::Upload(code) => Self::Upload(code.0),
+			Code::Existing(code_hash) => Self::Existing(code_hash),
+		}
+	}
+}
+
+/// A contract instantiation to dry run.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[serde(deny_unknown_fields)]
+pub struct InstantiateRequest<AccountId, Balance, Hash> {
+	pub origin: AccountId,
+	pub value: Balance,
+	pub gas_limit: Option<Weight>,
+	pub storage_deposit_limit: Option<Balance>,
+	pub code: Code<Hash>,
+	pub data: Bytes,
+	pub salt: Bytes,
+}
+
+/// The result of a dry run.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CallResult<Balance> {
+	pub gas_consumed: Weight,
+	pub gas_required: Weight,
+	pub storage_deposit: Deposit<Balance>,
+	pub debug_message: String,
+	/// The flags the contract returned with.
+	pub flags: u32,
+	/// The output of the contract.
+	pub data: Bytes,
+	/// The error the call failed with, without flags and data then.
+	pub error: Option<DispatchError>,
+}
+
+/// Contracts RPC methods.
+#[rpc(server)]
+pub trait ContractsDevRpc<BlockHash, AccountId, Balance> {
+	/// Dry run a call and return the tree of all contract calls it made, with the value, gas and
+	/// storage deposit of each.
+	///
+	/// Without `gas_limit`, the call may use the maximum weight of a block.
+	#[method(name = "contracts_traceCall")]
+	fn trace_call(
+		&self,
+		call_request: CallRequest<AccountId, Balance>,
+		at: Option<BlockHash>,
+	) -> RpcResult<TracedCall<AccountId, Balance>>;
+
+	/// Dry run a call and split the gas it consumed by the kind of cost.
+	#[method(name = "contracts_profileCall")]
+	fn profile_call(
+		&self,
+		call_request: CallRequest<AccountId, Balance>,
+		at: Option<BlockHash>,
+	) -> RpcResult<CallProfile>;
+
+	/// Like `contracts_profileCall`, in the folded stack format of `flamegraph.pl` and `inferno`.
+	#[method(name = "contracts_profileCallFolded")]
+	fn profile_call_folded(
+		&self,
+		call_request: CallRequest<AccountId, Balance>,
+		at: Option<BlockHash>,
+	) -> RpcResult<String>;
+
+	/// Dry run a call like `ContractsApi_call`, with `determinism`.
+	///
+	/// With `Relaxed`, the call may execute code uploaded with `Determinism::Relaxed`. Without
+	/// `determinism`, the call runs with `Enforced` like on-chain.
+	#[method(name = "contracts_call")]
+	fn call(
+		&self,
+		call_request: CallRequest<AccountId, Balance>,
+		determinism: Option<Determinism>,
+		at: Option<BlockHash>,
+	) -> RpcResult<CallResult<Balance>>;
+
+	/// The smallest gas limit and the storage deposit a call needs.
+	///
+	/// Gas limits are searched up to `gas_limit`, or the maximum weight of a block without it.
+	#[method(name = "contracts_estimateCall")]
+	fn estimate_call(
+		&self,
+		call_request: CallRequest<AccountId, Balance>,
+		at: Option<BlockHash>,
+	) -> RpcResult<GasEstimate<Balance>>;
+
+	/// The smallest gas limit and the storage deposit an instantiation needs.
+	///
+	/// Gas limits are searched up to `gas_limit`, or the maximum weight of a block without it.
+	#[method(name = "contracts_estimateInstantiate")]
+	fn estimate_instantiate(
+		&self,
+		instantiate_request: InstantiateRequest<AccountId, Balance, BlockHash>,
+		at: Option<BlockHash>,
+	) -> RpcResult<GasEstimate<Balance>>;
+}
+
+/// Serves [`ContractsDevRpcServer`] with the runtime API of `client`.
+pub struct ContractsDev<C, Block> {
+	client: Arc<C>,
+	_marker: PhantomData<Block>,
+}
+
+impl<C, Block> ContractsDev<C, Block> {
+	pub fn new(client: Arc<C>) -> Self {
+		Self { client, _marker: Default::default() }
+	}
+}
+
+impl<C, Block, AccountId, Balance> ContractsDevRpcServer<Block::Hash, AccountId, Balance>
+	for ContractsDev<C, Block>
+where
+	Block: BlockT,
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + Send + Sync + 'static,
+	C::Api: ContractsDevApi<Block, AccountId, Balance, NumberFor<Block>, Block::Hash>,
+	AccountId: Codec + Clone + Send + Sync + Serialize + for<'de> Deserialize<'de> + 'static,
+	Balance: Codec + Clone + Send + Sync + Serialize + for<'de> Deserialize<'de> + 'static,
+{
+	fn trace_call(
+		&self,
+		call_request: CallRequest<AccountId, Balance>,
+		at: Option<Block::Hash>,
+	) -> RpcResult<TracedCall<AccountId, Balance>> {
+		let at = at.unwrap_or_else(|| self.client.info().best_hash);
+		let CallRequest { origin, dest, value, gas_limit, storage_deposit_limit, input_data } =
+			call_request;
+		self.client
+			.runtime_api()
+			.trace_call(at, origin, dest, value, gas_limit, storage_deposit_limit, input_data.0)
+			.map_err(runtime_error)
+	}
+
+	fn profile_call(
+		&self,
+		call_request: CallRequest<AccountId, Balance>,
+		at: Option<Block::Hash>,
+	) -> RpcResult<CallProfile> {
+		let at = at.unwrap_or_else(|| self.client.info().best_hash);
+		let CallRequest { origin, dest, value, gas_limit, storage_deposit_limit, input_data } =
+			call_request;
+		self.client
+			.runtime_api()
+			.profile_call(at, origin, dest, value, gas_limit, storage_deposit_limit, input_data.0)
+			.map_err(runtime_error)
+	}
+
+	fn profile_call_folded(
+		&self,
+		call_request: CallRequest<AccountId, Balance>,
+		at: Option<Block::Hash>,
+	) -> RpcResult<String> {
+		Ok(self.profile_call(call_request, at)?.folded("call"))
+	}
+
+	fn call(
+		&self,
+		call_request: CallRequest<AccountId, Balance>,
+		determinism: Option<Determinism>,
+		at: Option<Block::Hash>,
+	) -> RpcResult<CallResult<Balance>> {
+		let at = at.unwrap_or_else(|| self.client.info().best_hash);
+		let CallRequest { origin, dest, value, gas_limit, storage_deposit_limit, input_data } =
+			call_request;
+		let result = self
+			.client
+			.runtime_api()
+			.call_with_determinism(
+				at,
+				origin,
+				dest,
+				value,
+				gas_limit,
+				storage_deposit_limit,
+				input_data.0,
+				determinism.unwrap_or(Determinism::Enforced),
+			)
+			.map_err(runtime_error)?;
+		let (flags, data, error) = match result.result {
+			Ok(output) => (output.flags.bits(), output.data, None),
+			Err(err) => (0, Vec::new(), Some(err)),
+		};
+		Ok(CallResult {
+			gas_consumed: result.gas_consumed,
+			gas_required: result.gas_required,
+			storage_deposit: result.storage_deposit.into(),
+			debug_message: String::from_utf8_lossy(&result.debug_message).into_owned(),
+			flags,
+			data: data.into(),
+			error,
+		})
+	}
+
+	fn estimate_call(
+		&self,
+		call_request: CallRequest<AccountId, Balance>,
+		at: Option<Block::Hash>,
+	) -> RpcResult<GasEstimate<Balance>> {
+		let at = at.unwrap_or_else(|| self.client.info().best_hash);
+		let CallRequest { origin, dest, value, gas_limit, storage_deposit_limit, input_data } =
+			call_request;
+		self.client
+			.runtime_api()
+			.estimate_call(at, origin, dest, value, gas_limit, storage_deposit_limit, input_data.0)
+			.map_err(runtime_error)
+	}
+
+	fn estimate_instantiate(
+		&self,
+		instantiate_request: InstantiateRequest<AccountId, Balance, Block::Hash>,
+		at: Option<Block::Hash>,
+	) -> RpcResult<GasEstimate<Balance>> {
+		let at = at.unwrap_or_else(|| self.client.info().best_hash);
+		let InstantiateRequest {
+			origin,
+			value,
+			gas_limit,
+			storage_deposit_limit,
+			code,
+			data,
+			salt,
+		} = instantiate_request;
+		self.client
+			.runtime_api()
+			.estimate_instantiate(
+				at,
+				origin,
+				value,
+				gas_limit,
+				storage_deposit_limit,
+				code.into(),
+				data.0,
+				salt.0,
+			)
+			.map_err(runtime_error)
+	}
+}
+
+pub(super) fn runtime_error(err: sp_api::ApiError) -> ErrorObjectOwned {
+	ErrorObject::owned(RUNTIME_ERROR, "Runtime error", Some(err.to_string()))
+}
@@ -0,0 +1,382 @@
+//! # Account Mapping Pallet
+//!
+//! Solidity's `address` has 20 bytes, while the accounts of the contracts node have 32. This pallet
+//! maps between the two in both directions.
+//!
+//! Without any registration, every account has an address and every address has an account:
+//!
+//! - An account that ends with 12 `0xee` bytes has the address of its first 20 bytes, e.g. the
+//!   accounts of contracts deployed with the `eth-addresses` feature. Any other account has the
+//!   last 20 bytes of its keccak256 hash, see [`derived_address`].
+//! - An address has the account of its 20 bytes padded with 12 `0xee` bytes, see
+//!   [`padded_account`].
+//!
+//! The hash can not be reversed, so an account registers its address to be found by it:
+//!
+//! - `map_account` registers the address derived from the account.
+//! - `claim_ecdsa` registers the address of an Ethereum key instead, proven by a `personal_sign`
+//!   signature of [`claim_message`].
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use sp_core::H160;
+use sp_io::hashing::keccak_256;
+use sp_runtime::AccountId32;
+use sp_std::vec::Vec;
+
+pub use pallet::*;
+
+/// The text an Ethereum key signs to claim its address for an account, followed by the account in
+/// hex.
+pub const CLAIM_PREFIX: &[u8] = b"Map to account ";
+
+/// The address of `account` when it did not register one.
+pub fn derived_address(account: &AccountId32) -> H160 {
+	let account: &[u8; 32] = account.as_ref();
+	if account[20..] == [0xee; 12] {
+		H160::from_slice(&account[..20])
+	} else {
+		H160::from_slice(&keccak_256(account)[12..])
+	}
+}
+
+/// The account of `address` when no account registered it.
+pub fn padded_account(address: &H160) -> AccountId32 {
+	let mut account = [0xee; 32];
+	account[..20].copy_from_slice(address.as_bytes());
+	account.into()
+}
+
+/// The message an Ethereum key signs with `personal_sign` to claim its address for `account`.
+pub fn claim_message(account: &AccountId32) -> Vec<u8> {
+	const HEX: &[u8; 16] = b"0123456789abcdef";
+	let account: &[u8; 32] = account.as_ref();
+	// The text always has 79 bytes.
+	let mut message = b"\x19Ethereum Signed Message:\n79".to_vec();
+	message.extend_from_slice(CLAIM_PREFIX);
+	for byte in account {
+		message.extend([HEX[(byte >> 4) as usize], HEX[(byte & 0xf) as usize]]);
+	}
+	message
+}
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::*;
+	use frame_support::pallet_prelude::*;
+	use frame_system::pallet_prelude::*;
+
+	/// Roughly the weight of recovering the public key of a signature.
+	const ECDSA_RECOVER_WEIGHT: Weight = Weight::from_parts(50_000_000, 0);
+
+	#[pallet::pallet]
+	pub struct Pallet<T>(_);
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config<AccountId = AccountId32> {
+		/// The overarching event type.
+		type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
+	}
+
+	/// The account that registered an address.
+	#[pallet::storage]
+	pub type Accounts<T: Config> = StorageMap<_, Blake2_128Concat, H160, AccountId32, OptionQuery>;
+
+	/// The address an account registered.
+	#[pallet::storage]
+	pub type Addresses<T: Config> = StorageMap<_, Blake2_128Concat, AccountId32, H160, OptionQuery>;
+
+	#[pallet::genesis_config]
+	#[derive(frame_support::DefaultNoBound)]
+	pub struct GenesisConfig<T: Config> {
+		/// The addresses registered from genesis on. An account listed twice keeps the last one.
+		pub mappings: Vec<(AccountId32, H160)>,
+		#[serde(skip)]
+		pub _config: PhantomData<T>,
+	}
+
+	#[pallet::genesis_build]
+	impl<T: Config> BuildGenesisConfig for GenesisConfig<T> {
+		fn build(&self) {
+			for (account, address) in &self.mappings {
+				assert!(
+					Pallet::<T>::insert(account, *address).is_ok(),
+					"address mapped to two accounts in genesis"
+				);
+			}
+		}
+	}
+
+	#[pallet::event]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// The account registered the address.
+		Mapped { account: AccountId32, address: H160 },
+		/// The account dropped the address it registered.
+		Unmapped { account: AccountId32, address: H160 },
+	}
+
+	#[pallet::error]
+	pub enum Error<T> {
+		/// Another account registered the address.
+		AddressTaken,
+		/// The signature is not a valid signature of the claim message.
+		BadSignature,
+		/// The account did not register an address.
+		NotMapped,
+	}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Register the address derived from the account of the origin, replacing the address it
+		/// registered before.
+		#[pallet::call_index(0)]
+		#[pallet::weight(T::DbWeight::get().reads_writes(2, 3))]
+		pub fn map_account(origin: OriginFor<T>) -> DispatchResult {
+			let account = ensure_signed(origin)?;
+			let address = derived_address(&account);
+			Self::map(account, address)
+		}
+
+		/// Register the address of the Ethereum key that signed the [`claim_message`] of the
+		/// account of the origin, replacing the address it registered before.
+		#[pallet::call_index(1)]
+		#[pallet::weight(
+			T::DbWeight::get().reads_writes(2, 3).saturating_add(ECDSA_RECOVER_WEIGHT)
+		)]
+		pub fn claim_ecdsa(origin: OriginFor<T>, signature: [u8; 65]) -> DispatchResult {
+			let account = ensure_signed(origin)?;
+			let hash = keccak_256(&claim_message(&account));
+			let public = sp_io::crypto::secp256k1_ecdsa_recover(&signature, &hash)
+				.map_err(|_| Error::<T>::BadSignature)?;
+			let address = H160::from_slice(&keccak_256(&public)[12..]);
+			Self::map(account, address)
+		}
+
+		/// Drop the address registered by the account of the origin.
+		#[pallet::call_index(2)]
+		#[pallet::weight(T::DbWeight::get().writes(2))]
+		pub fn unmap(origin: OriginFor<T>) -> DispatchResult {
+			let account = ensure_signed(origin)?;
+			let address = Addresses::<T>::take(&account).ok_or(Error::<T>::NotMapped)?;
+			Accounts::<T>::remove(address);
+			Self::deposit_event(Event::Unmapped { account, address });
+			Ok(())
+		}
+	}
+
+	impl<T: Config> Pallet<T> {
+		fn map(account: AccountId32, address: H160) -> DispatchResult {
+			if Self::insert(&account, address)? {
+				Self::deposit_event(Event::Mapped { account, address });
+			}
+			Ok(())
+		}
+
+		/// Register `address` for `account`, dropping the address it registered before. Returns
+		/// whether the mapping changed.
+		fn insert(account: &AccountId32, address: H160) -> Result<bool, Error<T>> {
+			match Accounts::<T>::get(address) {
+				Some(owner) if owner == *account => return Ok(false),
+				Some(_) => return Err(Error::<T>::AddressTaken),
+				None => (),
+			}
+			if let Some(old) = Addresses::<T>::get(account) {
+				Accounts::<T>::remove(old);
+			}
+			Accounts::<T>::insert(address, account);
+			Addresses::<T>::insert(account, address);
+			Ok(true)
+		}
+
+		/// The address of `account`: the one it registered, or the one derived from it.
+		pub fn address_of(account: &AccountId32) -> H160 {
+			Addresses::<T>::get(account).unwrap_or_else(|| derived_address(account))
+		}
+
+		/// The account of `address`: the one that registered it, or the padded address.
+		pub fn account_of(address: &H160) -> AccountId32 {
+			Accounts::<T>::get(address).unwrap_or_else(|| padded_account(address))
+		}
+	}
+}
+
+sp_api::decl_runtime_apis! {
+	/// The mapping between Ethereum addresses and accounts.
+	pub trait AccountMappingApi {
+		/// The address of `account`.
+		fn address_of(account: AccountId32) -> H160;
+
+		/// The account of `address`.
+		fn account_of(address: H160) -> AccountId32;
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use frame_support::{assert_noop, assert_ok, construct_runtime, derive_impl};
+	use hex_literal::hex;
+	use sp_runtime::{traits::IdentityLookup, BuildStorage, DispatchError};
+
+	type Block = frame_system::mocking::MockBlock<Test>;
+
+	construct_runtime!(
+		pub enum Test {
+			System: frame_system,
+			AccountMapping: crate,
+		}
+	);
+
+	#[derive_impl(frame_system::config_preludes::TestDefaultConfig as frame_system::DefaultConfig)]
+	impl frame_system::Config for Test {
+		type Block = Block;
+		type AccountId = AccountId32;
+		type Lookup = IdentityLookup<AccountId32>;
+	}
+
+	impl Config for Test {
+		type RuntimeEvent = RuntimeEvent;
+	}
+
+	const ALICE: AccountId32 = AccountId32::new([1; 32]);
+	const BOB: AccountId32 = AccountId32::new([2; 32]);
+
+	/// The address of the first development account of Hardhat and Anvil, whose private key is
+	/// `0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80`.
+	const KEY_ADDRESS: H160 = H160(hex!("f39fd6e51aad88f6f4ce6ab8827279cfffb92266"));
+
+	/// The `personal_sign` signature of the claim message of `ALICE` by the key of
+	/// `KEY_ADDRESS`.
+	const ALICE_CLAIM: [u8; 65] = hex!(
+		"a4470d330ffeca3b3bf1537601680b21f3569bc59d446096a47d525b74c717ea"
+		"762b43f194c18c02edc724240561a059807e15473c6883dcc58ccf9653efdeba1c"
+	);
+
+	fn new_test_ext(mappings: Vec<(AccountId32, H160)>) -> sp_io::TestExternalities {
+		let storage = RuntimeGenesisConfig {
+			system: Default::default(),
+			account_mapping: GenesisConfig { mappings, ..Default::default() },
+		}
+		.build_storage()
+		.unwrap();
+		let mut ext = sp_io::TestExternalities::from(storage);
+		ext.execute_with(|| System::set_block_number(1));
+		ext
+	}
+
+	#[test]
+	fn unregistered_accounts_and_addresses() {
+		let contract = padded_account(&KEY_ADDRESS);
+		assert_eq!(
+			contract,
+			AccountId32::new(hex!(
+				"f39fd6e51aad88f6f4ce6ab8827279cfffb92266eeeeeeeeeeeeeeeeeeeeeeee"
+			))
+		);
+		assert_eq!(derived_address(&contract), KEY_ADDRESS);
+
+		new_test_ext(vec![]).execute_with(|| {
+			assert_eq!(AccountMapping::address_of(&ALICE), derived_address(&ALICE));
+			assert_eq!(AccountMapping::account_of(&KEY_ADDRESS), contract);
+		});
+	}
+
+	#[test]
+	fn claim_message_is_prefixed_with_its_length() {
+		let message = claim_message(&ALICE);
+		let text = message.strip_prefix(b"\x19Ethereum Signed Message:\n79").unwrap();
+		assert_eq!(text.len(), 79);
+		assert_eq!(
+			text,
+			&b"Map to account 0101010101010101010101010101010101010101010101010101010101010101"[..]
+		);
+	}
+
+	#[test]
+	fn claim_ecdsa_maps_the_signing_key() {
+		new_test_ext(vec![]).execute_with(|| {
+			assert_ok!(AccountMapping::claim_ecdsa(RuntimeOrigin::signed(ALICE), ALICE_CLAIM));
+			System::assert_last_event(
+				Event::<Test>::Mapped { account: ALICE, address: KEY_ADDRESS }.into(),
+			);
+			assert_eq!(AccountMapping::address_of(&ALICE), KEY_ADDRESS);
+			assert_eq!(AccountMapping::account_of(&KEY_ADDRESS), ALICE);
+
+			assert_noop!(
+				AccountMapping::claim_ecdsa(RuntimeOrigin::signed(BOB), [0; 65]),
+				Error::<Test>::BadSignature
+			);
+			// The signature is bound to the account: replayed by another one, it recovers another
+			// key.
+			assert_ok!(AccountMapping::claim_ecdsa(RuntimeOrigin::signed(BOB), ALICE_CLAIM));
+			assert_ne!(AccountMapping::address_of(&BOB), KEY_ADDRESS);
+			assert_eq!(AccountMapping::account_of(&KEY_ADDRESS), ALICE);
+		});
+	}
+
+	#[test]
+	fn map_and_unmap_round_trip() {
+		new_test_ext(vec![]).execute_with(|| {
+			let derived = derived_address(&ALICE);
+			assert_ok!(AccountMapping::map_account(RuntimeOrigin::signed(ALICE)));
+			System::assert_last_event(
+				Event::<Test>::Mapped { account: ALICE, address: derived }.into(),
+			);
+			assert_eq!(AccountMapping::account_of(&derived), ALICE);
+			assert_eq!(AccountMapping::address_of(&ALICE), derived);
+
+			// Mapping again changes nothing.
+			System::reset_events();
+			assert_ok!(AccountMapping::map_account(RuntimeOrigin::signed(ALICE)));
+			assert!(System::events().is_empty());
+
+			// A claim replaces the address, freeing the old one.
+			assert_ok!(AccountMapping::claim_ecdsa(RuntimeOrigin::signed(ALICE), ALICE_CLAIM));
+			assert_eq!(AccountMapping::address_of(&ALICE), KEY_ADDRESS);
+			assert_eq!(Accounts::<Test>::get(derived), None);
+			assert_eq!(AccountMapping::account_of(&derived), padded_account(&derived));
+
+			assert_ok!(AccountMapping::unmap(RuntimeOrigin::signed(ALICE)));
+			System::assert_last_event(
+				Event::<Test>::Unmapped { account: ALICE, address: KEY_ADDRESS }.into(),
+			);
+			assert_eq!(AccountMapping::address_of(&ALICE), derived);
+			assert_eq!(AccountMapping::account_of(&KEY_ADDRESS), padded_account(&KEY_ADDRESS));
+			assert_noop!(
+				AccountMapping::unmap(RuntimeOrigin::signed(ALICE)),
+				Error::<Test>::NotMapped
+			);
+			assert_noop!(
+				AccountMapping::map_account(RuntimeOrigin::none()),
+				DispatchError::BadOrigin
+			);
+		});
+	}
+
+	#[test]
+	fn address_of_another_account_is_taken() {
+		new_test_ext(vec![(BOB, KEY_ADDRESS)]).execute_with(|| {
+			assert_noop!(
+				AccountMapping::claim_ecdsa(RuntimeOrigin::signed(ALICE), ALICE_CLAIM),
+				Error::<Test>::AddressTaken
+			);
+		});
+	}
+
+	#[test]
+	fn genesis_replaces_the_address_of_an_account_listed_twice() {
+		let derived = derived_address(&ALICE);
+		new_test_ext(vec![(ALICE, derived), (ALICE, KEY_ADDRESS)]).execute_with(|| {
+			assert_eq!(AccountMapping::address_of(&ALICE), KEY_ADDRESS);
+			assert_eq!(Accounts::<Test>::get(derived), None);
+			assert_eq!(Accounts::<Test>::get(KEY_ADDRESS), Some(ALICE));
+		});
+	}
+
+	#[test]
+	#[should_panic(expected = "address mapped to two accounts in genesis")]
+	fn genesis_rejects_an_address_listed_for_two_accounts() {
+		new_test_ext(vec![(ALICE, KEY_ADDRESS), (BOB, KEY_ADDRESS)]);
+	}
+}
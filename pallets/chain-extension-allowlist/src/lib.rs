@@ -0,0 +1,305 @@
+//! # Chain Extension Allowlist Pallet
+//!
+//! Every contract can call every function of the runtime's chain extension by default. This
+//! pallet lets the admin origin (sudo on the contracts node) mark individual function ids as
+//! restricted. A restricted function may only be called by contracts that are allowlisted for it,
+//! either by their account or by the hash of the code they were instantiated from.
+//!
+//! This way experimental or dev-only functions can be part of the runtime without being exposed
+//! to every contract.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::{Decode, Encode, MaxEncodedLen};
+use scale_info::TypeInfo;
+use sp_runtime::RuntimeDebug;
+
+pub use pallet::*;
+
+/// Who an allowlist entry applies to.
+#[derive(Clone, Encode, Decode, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub enum Subject<AccountId, Hash> {
+	/// A single contract account.
+	Contract(AccountId),
+	/// Every contract instantiated from the code with this hash.
+	CodeHash(Hash),
+}
+
+/// The [`Subject`] type of a runtime.
+pub type SubjectOf<T> =
+	Subject<<T as frame_system::Config>::AccountId, <T as frame_system::Config>::Hash>;
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::*;
+	use frame_support::pallet_prelude::*;
+	use frame_system::pallet_prelude::*;
+	use sp_std::vec::Vec;
+
+	#[pallet::pallet]
+	pub struct Pallet<T>(_);
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		/// The overarching event type.
+		type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
+
+		/// The origin which may change the allowlist.
+		type AdminOrigin: EnsureOrigin<Self::RuntimeOrigin>;
+	}
+
+	/// Function ids which are only available to allowlisted contracts.
+	#[pallet::storage]
+	pub type Restricted<T: Config> = StorageMap<_, Twox64Concat, u16, (), OptionQuery>;
+
+	/// The contracts and code hashes allowed to call a restricted function id.
+	#[pallet::storage]
+	pub type Allowed<T: Config> =
+		StorageDoubleMap<_, Twox64Concat, u16, Blake2_128Concat, SubjectOf<T>, (), OptionQuery>;
+
+	#[pallet::genesis_config]
+	#[derive(frame_support::DefaultNoBound)]
+	pub struct GenesisConfig<T: Config> {
+		/// Function ids which are restricted from genesis on.
+		pub restricted: Vec<u16>,
+		/// Contract accounts allowed to call a restricted function id.
+		pub allowed_contracts: Vec<(u16, T::AccountId)>,
+		/// Code hashes allowed to call a restricted function id.
+		pub allowed_code_hashes: Vec<(u16, T::Hash)>,
+	}
+
+	#[pallet::genesis_build]
+	impl<T: Config> BuildGenesisConfig for GenesisConfig<T> {
+		fn build(&self) {
+			for func_id in &self.restricted {
+				Restricted::<T>::insert(func_id, ());
+			}
+			for (func_id, contract) in &self.allowed_contracts {
+				Allowed::<T>::insert(func_id, Subject::Contract(contract.clone()), ());
+			}
+			for (func_id, code_hash) in &self.allowed_code_hashes {
+				Allowed::<T>::insert(func_id, Subject::CodeHash(*code_hash), ());
+			}
+		}
+	}
+
+	#[pallet::event]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// The function is now only available to allowlisted contracts.
+		Restricted { func_id: u16 },
+		/// The function is available to every contract again.
+		Unrestricted { func_id: u16 },
+		/// A contract or code hash was allowed to call the function.
+		Allowed { func_id: u16, subject: SubjectOf<T> },
+		/// A contract or code hash is no longer allowed to call the function.
+		Disallowed { func_id: u16, subject: SubjectOf<T> },
+	}
+
+	#[pallet::error]
+	pub enum Error<T> {
+		/// The calling contract is not allowlisted for this chain extension function.
+		NotAllowed,
+	}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Restrict a function id to allowlisted contracts, or lift the restriction.
+		///
+		/// Lifting the restriction keeps the existing allowlist entries, so restricting the
+		/// function again restores the previous set of allowed contracts.
+		#[pallet::call_index(0)]
+		#[pallet::weight(T::DbWeight::get().writes(1))]
+		pub fn set_restricted(
+			origin: OriginFor<T>,
+			func_id: u16,
+			restricted: bool,
+		) -> DispatchResult {
+			T::AdminOrigin::ensure_origin(origin)?;
+			if restricted {
+				Restricted::<T>::insert(func_id, ());
+				Self::deposit_event(Event::Restricted { func_id });
+			} else {
+				Restricted::<T>::remove(func_id);
+				Self::deposit_event(Event::Unrestricted { func_id });
+			}
+			Ok(())
+		}
+
+		/// Allow a contract or all contracts of a code hash to call a restricted function id.
+		#[pallet::call_index(1)]
+		#[pallet::weight(T::DbWeight::get().writes(1))]
+		pub fn allow(origin: OriginFor<T>, func_id: u16, subject: SubjectOf<T>) -> DispatchResult {
+			T::AdminOrigin::ensure_origin(origin)?;
+			Allowed::<T>::insert(func_id, &subject, ());
+			Self::deposit_event(Event::Allowed { func_id, subject });
+			Ok(())
+		}
+
+		/// Remove an allowlist entry.
+		#[pallet::call_index(2)]
+		#[pallet::weight(T::DbWeight::get().writes(1))]
+		pub fn disallow(
+			origin: OriginFor<T>,
+			func_id: u16,
+			subject: SubjectOf<T>,
+		) -> DispatchResult {
+			T::AdminOrigin::ensure_origin(origin)?;
+			Allowed::<T>::remove(func_id, &subject);
+			Self::deposit_event(Event::Disallowed { func_id, subject });
+			Ok(())
+		}
+	}
+
+	impl<T: Config> Pallet<T> {
+		/// Whether `contract`, instantiated from `code_hash`, may call `func_id`.
+		pub fn is_allowed(func_id: u16, contract: &T::AccountId, code_hash: &T::Hash) -> bool {
+			!Restricted::<T>::contains_key(func_id) ||
+				Allowed::<T>::contains_key(func_id, Subject::Contract(contract.clone())) ||
+				Allowed::<T>::contains_key(func_id, Subject::CodeHash(*code_hash))
+		}
+
+		/// Like [`Self::is_allowed`] but fails with [`Error::NotAllowed`].
+		pub fn ensure_allowed(
+			func_id: u16,
+			contract: &T::AccountId,
+			code_hash: &T::Hash,
+		) -> DispatchResult {
+			ensure!(Self::is_allowed(func_id, contract, code_hash), Error::<T>::NotAllowed);
+			Ok(())
+		}
+
+		/// The worst case weight of [`Self::is_allowed`].
+		pub fn check_weight() -> Weight {
+			T::DbWeight::get().reads(3)
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use frame_support::{assert_noop, assert_ok, construct_runtime, derive_impl};
+	use frame_system::EnsureRoot;
+	use sp_runtime::{testing::H256, BuildStorage, DispatchError};
+
+	type Block = frame_system::mocking::MockBlock<Test>;
+
+	construct_runtime!(
+		pub enum Test {
+			System: frame_system,
+			Allowlist: crate,
+		}
+	);
+
+	#[derive_impl(frame_system::config_preludes::TestDefaultConfig as frame_system::DefaultConfig)]
+	impl frame_system::Config for Test {
+		type Block = Block;
+	}
+
+	impl Config for Test {
+		type RuntimeEvent = RuntimeEvent;
+		type AdminOrigin = EnsureRoot<u64>;
+	}
+
+	const FUNC: u16 = 1101;
+	const CONTRACT: u64 = 1;
+	const OTHER: u64 = 2;
+	const CODE: H256 = H256::repeat_byte(1);
+	const OTHER_CODE: H256 = H256::repeat_byte(2);
+
+	fn new_test_ext(genesis: GenesisConfig<Test>) -> sp_io::TestExternalities {
+		let storage = RuntimeGenesisConfig { system: Default::default(), allowlist: genesis }
+			.build_storage()
+			.unwrap();
+		let mut ext = sp_io::TestExternalities::from(storage);
+		ext.execute_with(|| System::set_block_number(1));
+		ext
+	}
+
+	#[test]
+	fn unrestricted_functions_are_allowed() {
+		new_test_ext(Default::default()).execute_with(|| {
+			assert!(Allowlist::is_allowed(FUNC, &CONTRACT, &CODE));
+			assert_ok!(Allowlist::ensure_allowed(FUNC, &OTHER, &OTHER_CODE));
+		});
+	}
+
+	#[test]
+	fn contract_entry_allows_only_that_account() {
+		new_test_ext(Default::default()).execute_with(|| {
+			assert_ok!(Allowlist::set_restricted(RuntimeOrigin::root(), FUNC, true));
+			assert_ok!(Allowlist::allow(RuntimeOrigin::root(), FUNC, Subject::Contract(CONTRACT)));
+
+			assert!(Allowlist::is_allowed(FUNC, &CONTRACT, &CODE));
+			assert!(Allowlist::is_allowed(FUNC, &CONTRACT, &OTHER_CODE));
+			assert!(!Allowlist::is_allowed(FUNC, &OTHER, &CODE));
+			assert_noop!(Allowlist::ensure_allowed(FUNC, &OTHER, &CODE), Error::<Test>::NotAllowed);
+			// Other function ids are not affected.
+			assert!(Allowlist::is_allowed(FUNC + 1, &OTHER, &CODE));
+		});
+	}
+
+	#[test]
+	fn code_hash_entry_allows_every_instance() {
+		new_test_ext(Default::default()).execute_with(|| {
+			assert_ok!(Allowlist::set_restricted(RuntimeOrigin::root(), FUNC, true));
+			assert_ok!(Allowlist::allow(RuntimeOrigin::root(), FUNC, Subject::CodeHash(CODE)));
+
+			assert!(Allowlist::is_allowed(FUNC, &CONTRACT, &CODE));
+			assert!(Allowlist::is_allowed(FUNC, &OTHER, &CODE));
+			assert!(!Allowlist::is_allowed(FUNC, &CONTRACT, &OTHER_CODE));
+		});
+	}
+
+	#[test]
+	fn disallow_and_unrestrict() {
+		new_test_ext(Default::default()).execute_with(|| {
+			let subject = Subject::Contract(CONTRACT);
+			assert_ok!(Allowlist::set_restricted(RuntimeOrigin::root(), FUNC, true));
+			assert_ok!(Allowlist::allow(RuntimeOrigin::root(), FUNC, subject.clone()));
+			assert_ok!(Allowlist::set_restricted(RuntimeOrigin::root(), FUNC, false));
+			assert!(Allowlist::is_allowed(FUNC, &OTHER, &CODE));
+
+			// Restricting again restores the previous entries.
+			assert_ok!(Allowlist::set_restricted(RuntimeOrigin::root(), FUNC, true));
+			assert!(Allowlist::is_allowed(FUNC, &CONTRACT, &CODE));
+			assert!(!Allowlist::is_allowed(FUNC, &OTHER, &CODE));
+
+			assert_ok!(Allowlist::disallow(RuntimeOrigin::root(), FUNC, subject.clone()));
+			assert!(!Allowlist::is_allowed(FUNC, &CONTRACT, &CODE));
+			System::assert_last_event(Event::Disallowed { func_id: FUNC, subject }.into());
+		});
+	}
+
+	#[test]
+	fn changes_require_admin_origin() {
+		new_test_ext(Default::default()).execute_with(|| {
+			let origin = RuntimeOrigin::signed(CONTRACT);
+			let subject = Subject::Contract(CONTRACT);
+			assert_noop!(
+				Allowlist::set_restricted(origin.clone(), FUNC, true),
+				DispatchError::BadOrigin
+			);
+			assert_noop!(
+				Allowlist::allow(origin.clone(), FUNC, subject.clone()),
+				DispatchError::BadOrigin
+			);
+			assert_noop!(Allowlist::disallow(origin, FUNC, subject), DispatchError::BadOrigin);
+		});
+	}
+
+	#[test]
+	fn genesis_config() {
+		let genesis = GenesisConfig {
+			restricted: vec![FUNC],
+			allowed_contracts: vec![(FUNC, CONTRACT)],
+			allowed_code_hashes: vec![(FUNC, OTHER_CODE)],
+		};
+		new_test_ext(genesis).execute_with(|| {
+			assert!(Allowlist::is_allowed(FUNC, &CONTRACT, &CODE));
+			assert!(Allowlist::is_allowed(FUNC, &OTHER, &OTHER_CODE));
+			assert!(!Allowlist::is_allowed(FUNC, &OTHER, &CODE));
+		});
+	}
+}
@@ -0,0 +1,330 @@
+//! # Contracts Call Filter Pallet
+//!
+//! The `CallFilter` of `pallet-contracts` decides which calls contracts may dispatch with
+//! `call_runtime`. This pallet is such a filter whose allowed calls live in storage, so the admin
+//! origin (sudo on the contracts node) can change them without a runtime upgrade.
+//!
+//! Calls are identified by the name of their pallet in `construct_runtime!` and their function
+//! name, e.g. `("Balances", "transfer_allow_death")`. The filter also applies to calls nested in
+//! an allowed call, e.g. the calls of a `Utility::batch` each have to be allowed as well.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use frame_support::{traits::ConstU32, BoundedVec};
+use sp_std::vec::Vec;
+
+pub use pallet::*;
+
+/// The longest pallet or function name that can be allowed.
+pub const MAX_NAME_LEN: u32 = 64;
+
+/// A pallet or function name.
+pub type Name = BoundedVec<u8, ConstU32<MAX_NAME_LEN>>;
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::*;
+	use frame_support::{
+		pallet_prelude::*,
+		traits::{Contains, GetCallMetadata},
+	};
+	use frame_system::pallet_prelude::*;
+
+	#[pallet::pallet]
+	pub struct Pallet<T>(_);
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		/// The overarching event type.
+		type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
+
+		/// The origin which may change the allowed calls.
+		type AdminOrigin: EnsureOrigin<Self::RuntimeOrigin>;
+	}
+
+	/// The calls contracts may dispatch, by pallet and function name.
+	#[pallet::storage]
+	pub type AllowedCalls<T: Config> =
+		StorageDoubleMap<_, Blake2_128Concat, Name, Blake2_128Concat, Name, (), OptionQuery>;
+
+	#[pallet::genesis_config]
+	#[derive(frame_support::DefaultNoBound)]
+	pub struct GenesisConfig<T: Config> {
+		/// The calls allowed from genesis on.
+		pub allowed_calls: Vec<(Name, Name)>,
+		#[serde(skip)]
+		pub _config: PhantomData<T>,
+	}
+
+	#[pallet::genesis_build]
+	impl<T: Config> BuildGenesisConfig for GenesisConfig<T> {
+		fn build(&self) {
+			for (pallet, function) in &self.allowed_calls {
+				AllowedCalls::<T>::insert(pallet, function, ());
+			}
+		}
+	}
+
+	#[pallet::event]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// Contracts may now dispatch the call.
+		CallAllowed { pallet: Name, function: Name },
+		/// Contracts may no longer dispatch the call.
+		CallDisallowed { pallet: Name, function: Name },
+	}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Allow contracts to dispatch `function` of `pallet`.
+		#[pallet::call_index(0)]
+		#[pallet::weight(T::DbWeight::get().writes(1))]
+		pub fn allow_call(origin: OriginFor<T>, pallet: Name, function: Name) -> DispatchResult {
+			T::AdminOrigin::ensure_origin(origin)?;
+			AllowedCalls::<T>::insert(&pallet, &function, ());
+			Self::deposit_event(Event::CallAllowed { pallet, function });
+			Ok(())
+		}
+
+		/// Stop contracts from dispatching `function` of `pallet`.
+		#[pallet::call_index(1)]
+		#[pallet::weight(T::DbWeight::get().writes(1))]
+		pub fn disallow_call(origin: OriginFor<T>, pallet: Name, function: Name) -> DispatchResult {
+			T::AdminOrigin::ensure_origin(origin)?;
+			AllowedCalls::<T>::remove(&pallet, &function);
+			Self::deposit_event(Event::CallDisallowed { pallet, function });
+			Ok(())
+		}
+	}
+
+	impl<T: Config> Pallet<T> {
+		/// Whether contracts may dispatch `function` of `pallet`.
+		pub fn is_allowed(pallet: &str, function: &str) -> bool {
+			let (Ok(pallet), Ok(function)) = (
+				Name::try_from(pallet.as_bytes().to_vec()),
+				Name::try_from(function.as_bytes().to_vec()),
+			) else {
+				return false
+			};
+			AllowedCalls::<T>::contains_key(pallet, function)
+		}
+
+		/// All allowed calls, as `(pallet, function)` names.
+		pub fn allowed_calls() -> Vec<(Vec<u8>, Vec<u8>)> {
+			AllowedCalls::<T>::iter_keys()
+				.map(|(pallet, function)| (pallet.into_inner(), function.into_inner()))
+				.collect()
+		}
+	}
+
+	/// The `CallFilter` of `pallet-contracts`.
+	///
+	/// The storage read is covered by the weight `pallet-contracts` charges for `call_runtime`.
+	impl<T: Config, Call: GetCallMetadata> Contains<Call> for Pallet<T> {
+		fn contains(call: &Call) -> bool {
+			let metadata = call.get_call_metadata();
+			Self::is_allowed(metadata.pallet_name, metadata.function_name)
+		}
+	}
+}
+
+sp_api::decl_runtime_apis! {
+	/// The calls contracts may dispatch.
+	pub trait ContractsCallFilterApi {
+		/// All allowed calls, as `(pallet, function)` names.
+		fn allowed_calls() -> Vec<(Vec<u8>, Vec<u8>)>;
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use frame_support::{
+		assert_noop, assert_ok, construct_runtime, derive_impl,
+		traits::{Contains, OriginTrait},
+	};
+	use frame_system::EnsureRoot;
+	use sp_runtime::{traits::Dispatchable, BuildStorage, DispatchError};
+
+	type Block = frame_system::mocking::MockBlock<Test>;
+
+	construct_runtime!(
+		pub enum Test {
+			System: frame_system,
+			Utility: pallet_utility,
+			CallFilter: crate,
+		}
+	);
+
+	#[derive_impl(frame_system::config_preludes::TestDefaultConfig as frame_system::DefaultConfig)]
+	impl frame_system::Config for Test {
+		type Block = Block;
+	}
+
+	impl pallet_utility::Config for Test {
+		type RuntimeEvent = RuntimeEvent;
+		type RuntimeCall = RuntimeCall;
+		type PalletsOrigin = OriginCaller;
+		type WeightInfo = ();
+	}
+
+	impl Config for Test {
+		type RuntimeEvent = RuntimeEvent;
+		type AdminOrigin = EnsureRoot<u64>;
+	}
+
+	fn name(name: &str) -> Name {
+		name.as_bytes().to_vec().try_into().unwrap()
+	}
+
+	fn new_test_ext(allowed_calls: &[(&str, &str)]) -> sp_io::TestExternalities {
+		let call_filter = GenesisConfig {
+			allowed_calls: allowed_calls
+				.iter()
+				.map(|(pallet, function)| (name(pallet), name(function)))
+				.collect(),
+			..Default::default()
+		};
+		let storage = RuntimeGenesisConfig { system: Default::default(), call_filter }
+			.build_storage()
+			.unwrap();
+		let mut ext = sp_io::TestExternalities::from(storage);
+		ext.execute_with(|| System::set_block_number(1));
+		ext
+	}
+
+	fn remark() -> RuntimeCall {
+		RuntimeCall::System(frame_system::Call::remark { remark: vec![] })
+	}
+
+	fn batch(calls: Vec<RuntimeCall>) -> RuntimeCall {
+		RuntimeCall::Utility(pallet_utility::Call::batch { calls })
+	}
+
+	/// Dispatch `call` like `call_runtime` of `pallet-contracts` does, with the filter added to
+	/// the origin.
+	fn call_runtime(call: RuntimeCall) -> Result<(), DispatchError> {
+		let mut origin = RuntimeOrigin::signed(1);
+		origin.add_filter(|call| <CallFilter as Contains<RuntimeCall>>::contains(call));
+		call.dispatch(origin).map(|_| ()).map_err(|err| err.error)
+	}
+
+	#[test]
+	fn genesis_calls_are_allowed() {
+		new_test_ext(&[("System", "remark")]).execute_with(|| {
+			assert!(CallFilter::is_allowed("System", "remark"));
+			assert!(!CallFilter::is_allowed("System", "remark_with_event"));
+			assert!(!CallFilter::is_allowed("Utility", "remark"));
+			assert_eq!(CallFilter::allowed_calls(), vec![(b"System".to_vec(), b"remark".to_vec())]);
+		});
+	}
+
+	#[test]
+	fn allow_and_disallow_call() {
+		new_test_ext(&[]).execute_with(|| {
+			assert_ok!(CallFilter::allow_call(
+				RuntimeOrigin::root(),
+				name("System"),
+				name("remark")
+			));
+			System::assert_last_event(
+				Event::<Test>::CallAllowed { pallet: name("System"), function: name("remark") }
+					.into(),
+			);
+			assert!(CallFilter::is_allowed("System", "remark"));
+
+			assert_ok!(CallFilter::disallow_call(
+				RuntimeOrigin::root(),
+				name("System"),
+				name("remark")
+			));
+			System::assert_last_event(
+				Event::<Test>::CallDisallowed { pallet: name("System"), function: name("remark") }
+					.into(),
+			);
+			assert!(!CallFilter::is_allowed("System", "remark"));
+			assert!(CallFilter::allowed_calls().is_empty());
+		});
+	}
+
+	#[test]
+	fn only_admin_changes_calls() {
+		new_test_ext(&[("System", "remark")]).execute_with(|| {
+			assert_noop!(
+				CallFilter::allow_call(RuntimeOrigin::signed(1), name("System"), name("set_code")),
+				DispatchError::BadOrigin
+			);
+			assert_noop!(
+				CallFilter::disallow_call(RuntimeOrigin::signed(1), name("System"), name("remark")),
+				DispatchError::BadOrigin
+			);
+		});
+	}
+
+	#[test]
+	fn names_over_max_len_are_never_allowed() {
+		let longest = "a".repeat(MAX_NAME_LEN as usize);
+		let too_long = "a".repeat(MAX_NAME_LEN as usize + 1);
+		assert!(Name::try_from(too_long.as_bytes().to_vec()).is_err());
+
+		new_test_ext(&[(&longest, &longest)]).execute_with(|| {
+			assert!(CallFilter::is_allowed(&longest, &longest));
+			assert!(!CallFilter::is_allowed(&too_long, &longest));
+			assert!(!CallFilter::is_allowed(&longest, &too_long));
+		});
+	}
+
+	#[test]
+	fn contains_matches_runtime_calls() {
+		new_test_ext(&[("System", "remark")]).execute_with(|| {
+			assert!(<CallFilter as Contains<RuntimeCall>>::contains(&remark()));
+			let remark_with_event =
+				RuntimeCall::System(frame_system::Call::remark_with_event { remark: vec![] });
+			assert!(!<CallFilter as Contains<RuntimeCall>>::contains(&remark_with_event));
+			assert!(!<CallFilter as Contains<RuntimeCall>>::contains(&batch(vec![remark()])));
+		});
+	}
+
+	#[test]
+	fn nested_calls_are_filtered() {
+		new_test_ext(&[("Utility", "batch")]).execute_with(|| {
+			// The batch itself is allowed, the remark in it is not.
+			assert_ok!(call_runtime(batch(vec![remark()])));
+			System::assert_last_event(
+				pallet_utility::Event::BatchInterrupted {
+					index: 0,
+					error: frame_system::Error::<Test>::CallFiltered.into(),
+				}
+				.into(),
+			);
+
+			// Also in a batch nested in the batch.
+			assert_ok!(call_runtime(batch(vec![batch(vec![remark()])])));
+			System::assert_has_event(
+				pallet_utility::Event::BatchInterrupted {
+					index: 0,
+					error: frame_system::Error::<Test>::CallFiltered.into(),
+				}
+				.into(),
+			);
+
+			assert_ok!(CallFilter::allow_call(
+				RuntimeOrigin::root(),
+				name("System"),
+				name("remark")
+			));
+			assert_ok!(call_runtime(batch(vec![batch(vec![remark()]), remark()])));
+			System::assert_last_event(pallet_utility::Event::BatchCompleted.into());
+		});
+	}
+
+	#[test]
+	fn disallowed_calls_are_filtered() {
+		new_test_ext(&[]).execute_with(|| {
+			assert_eq!(
+				call_runtime(batch(vec![remark()])),
+				Err(frame_system::Error::<Test>::CallFiltered.into())
+			);
+		});
+	}
+}
@@ -0,0 +1,472 @@
+//! # Contracts Context Pallet
+//!
+//! Keeps the context of the current transaction and block that Solidity exposes as globals but
+//! `pallet-contracts` does not:
+//!
+//! - `tx.origin`: the signer of the outer extrinsic, recorded by the [`RecordTxOrigin`] signed
+//!   extension for the duration of the dispatch.
+//! - `block.coinbase`: on chains without a block author (like the dev node with instant seal),
+//!   [`DevAuthor`] reports a configurable account as author.
+//! - `block.chainid`: a stable chain identifier provided by the runtime, see [`GenesisHash`].
+//! - the state of the relay chain, on a parachain, see [`RelayChainContext`].
+//! - the tip of the current transaction (part of `tx.gasprice`), recorded by this pallet's
+//!   [`ChargeTransactionPayment`], a drop-in replacement for the one of
+//!   `pallet-transaction-payment`.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::{Decode, Encode};
+use core::marker::PhantomData;
+use frame_support::{
+	dispatch::{DispatchInfo, PostDispatchInfo},
+	traits::FindAuthor,
+	CloneNoBound, DefaultNoBound, EqNoBound, PartialEqNoBound, RuntimeDebugNoBound,
+};
+use frame_system::pallet_prelude::BlockNumberFor;
+use pallet_transaction_payment::{Config as TransactionPaymentConfig, OnChargeTransaction};
+use scale_info::TypeInfo;
+use sp_runtime::{
+	traits::{
+		DispatchInfoOf, Dispatchable, PostDispatchInfoOf, SignedExtension, UniqueSaturatedInto,
+		Zero,
+	},
+	transaction_validity::{TransactionValidity, TransactionValidityError},
+	ConsensusEngineId,
+};
+
+pub use pallet::*;
+
+type BalanceOf<T> =
+	<<T as TransactionPaymentConfig>::OnChargeTransaction as OnChargeTransaction<T>>::Balance;
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::*;
+	use frame_support::pallet_prelude::*;
+
+	#[pallet::pallet]
+	pub struct Pallet<T>(_);
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config + TransactionPaymentConfig {
+		/// A stable identifier of the chain (`block.chainid`), as a 32 byte little-endian word.
+		type ChainId: Get<[u8; 32]>;
+
+		/// The relay chain state, `()` if the chain is not a parachain.
+		type RelayChain: RelayChainContext;
+	}
+
+	/// The signer of the extrinsic that is currently being dispatched.
+	#[pallet::storage]
+	#[pallet::whitelist_storage]
+	pub type TxOrigin<T: Config> = StorageValue<_, T::AccountId, OptionQuery>;
+
+	/// The tip paid for the extrinsic that is currently being dispatched.
+	#[pallet::storage]
+	#[pallet::whitelist_storage]
+	pub type TxTip<T: Config> = StorageValue<_, u128, ValueQuery>;
+
+	/// The block author reported by [`DevAuthor`].
+	#[pallet::storage]
+	pub type Author<T: Config> = StorageValue<_, T::AccountId, OptionQuery>;
+
+	#[pallet::genesis_config]
+	#[derive(frame_support::DefaultNoBound)]
+	pub struct GenesisConfig<T: Config> {
+		/// The block author reported by [`DevAuthor`].
+		pub dev_author: Option<T::AccountId>,
+	}
+
+	#[pallet::genesis_build]
+	impl<T: Config> BuildGenesisConfig for GenesisConfig<T> {
+		fn build(&self) {
+			if let Some(author) = &self.dev_author {
+				Author::<T>::put(author);
+			}
+		}
+	}
+
+	impl<T: Config> Pallet<T> {
+		/// The signer of the extrinsic that is currently being dispatched, if it is signed.
+		pub fn tx_origin() -> Option<T::AccountId> {
+			TxOrigin::<T>::get()
+		}
+
+		/// The tip paid for the extrinsic that is currently being dispatched, zero if it is not
+		/// signed.
+		pub fn tx_tip() -> u128 {
+			TxTip::<T>::get()
+		}
+
+		/// The chain identifier, see [`Config::ChainId`].
+		pub fn chain_id() -> [u8; 32] {
+			T::ChainId::get()
+		}
+	}
+}
+
+/// The state of the relay chain a parachain block is built on.
+///
+/// All functions return `None` if the state is not known, e.g. on a standalone chain.
+pub trait RelayChainContext {
+	/// The number of the relay chain block the current block is built on.
+	fn relay_parent_number() -> Option<u32>;
+
+	/// The storage root of the relay chain block the current block is built on.
+	fn relay_parent_storage_root() -> Option<[u8; 32]>;
+
+	/// The `ParaId` of this chain.
+	fn para_id() -> Option<u32>;
+}
+
+impl RelayChainContext for () {
+	fn relay_parent_number() -> Option<u32> {
+		None
+	}
+
+	fn relay_parent_storage_root() -> Option<[u8; 32]> {
+		None
+	}
+
+	fn para_id() -> Option<u32> {
+		None
+	}
+}
+
+/// Reports the account configured at genesis as author of every block.
+///
+/// Only meant for development chains that have no block author of their own.
+pub struct DevAuthor<T>(PhantomData<T>);
+
+impl<T: Config> FindAuthor<T::AccountId> for DevAuthor<T> {
+	fn find_author<'a, I>(_digests: I) -> Option<T::AccountId>
+	where
+		I: 'a + IntoIterator<Item = (ConsensusEngineId, &'a [u8])>,
+	{
+		Author::<T>::get()
+	}
+}
+
+/// Uses the genesis hash as chain identifier.
+pub struct GenesisHash<T>(PhantomData<T>);
+
+impl<T: frame_system::Config> frame_support::traits::Get<[u8; 32]> for GenesisHash<T> {
+	fn get() -> [u8; 32] {
+		let mut id = [0u8; 32];
+		let hash = frame_system::BlockHash::<T>::get(BlockNumberFor::<T>::zero());
+		let hash = hash.as_ref();
+		let len = hash.len().min(32);
+		id[..len].copy_from_slice(&hash[..len]);
+		id
+	}
+}
+
+/// Records the signer of an extrinsic in [`TxOrigin`] while it is being dispatched.
+#[derive(
+	Encode,
+	Decode,
+	CloneNoBound,
+	EqNoBound,
+	PartialEqNoBound,
+	TypeInfo,
+	RuntimeDebugNoBound,
+	DefaultNoBound,
+)]
+#[scale_info(skip_type_params(T))]
+pub struct RecordTxOrigin<T>(PhantomData<T>);
+
+impl<T> RecordTxOrigin<T> {
+	/// Create the signed extension, e.g. when building an extrinsic.
+	pub fn new() -> Self {
+		Self(PhantomData)
+	}
+}
+
+impl<T: Config + Send + Sync> SignedExtension for RecordTxOrigin<T>
+where
+	T::RuntimeCall: Dispatchable<Info = DispatchInfo>,
+{
+	const IDENTIFIER: &'static str = "RecordTxOrigin";
+	type AccountId = T::AccountId;
+	type Call = T::RuntimeCall;
+	type AdditionalSigned = ();
+	type Pre = ();
+
+	fn additional_signed(&self) -> Result<(), TransactionValidityError> {
+		Ok(())
+	}
+
+	fn pre_dispatch(
+		self,
+		who: &Self::AccountId,
+		_call: &Self::Call,
+		_info: &DispatchInfoOf<Self::Call>,
+		_len: usize,
+	) -> Result<Self::Pre, TransactionValidityError> {
+		TxOrigin::<T>::put(who);
+		Ok(())
+	}
+
+	fn post_dispatch(
+		_pre: Option<Self::Pre>,
+		_info: &DispatchInfoOf<Self::Call>,
+		_post_info: &PostDispatchInfoOf<Self::Call>,
+		_len: usize,
+		_result: &sp_runtime::DispatchResult,
+	) -> Result<(), TransactionValidityError> {
+		TxOrigin::<T>::kill();
+		Ok(())
+	}
+}
+
+/// `pallet_transaction_payment::ChargeTransactionPayment`, which also records the tip in
+/// [`TxTip`] while the extrinsic is being dispatched.
+///
+/// It has the same identifier and encoding as the original, so wallets and other clients build
+/// extrinsics for it without any changes.
+#[derive(Encode, Decode, CloneNoBound, EqNoBound, PartialEqNoBound, TypeInfo)]
+#[scale_info(skip_type_params(T))]
+pub struct ChargeTransactionPayment<T: Config>(#[codec(compact)] BalanceOf<T>);
+
+impl<T: Config> ChargeTransactionPayment<T>
+where
+	BalanceOf<T>: Send + Sync,
+	T::RuntimeCall: Dispatchable<Info = DispatchInfo, PostInfo = PostDispatchInfo>,
+{
+	/// Create the signed extension with the given tip.
+	pub fn from(tip: BalanceOf<T>) -> Self {
+		Self(tip)
+	}
+
+	fn inner(&self) -> pallet_transaction_payment::ChargeTransactionPayment<T> {
+		pallet_transaction_payment::ChargeTransactionPayment::from(self.0)
+	}
+}
+
+impl<T: Config> core::fmt::Debug for ChargeTransactionPayment<T> {
+	#[cfg(feature = "std")]
+	fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+		write!(f, "ChargeTransactionPayment<{:?}>", self.0)
+	}
+	#[cfg(not(feature = "std"))]
+	fn fmt(&self, _: &mut core::fmt::Formatter) -> core::fmt::Result {
+		Ok(())
+	}
+}
+
+impl<T: Config> SignedExtension for ChargeTransactionPayment<T>
+where
+	BalanceOf<T>: Send + Sync + From<u64>,
+	T::RuntimeCall: Dispatchable<Info = DispatchInfo, PostInfo = PostDispatchInfo>,
+{
+	const IDENTIFIER: &'static str = "ChargeTransactionPayment";
+	type AccountId = T::AccountId;
+	type Call = T::RuntimeCall;
+	type AdditionalSigned = ();
+	type Pre = <pallet_transaction_payment::ChargeTransactionPayment<T> as SignedExtension>::Pre;
+
+	fn additional_signed(&self) -> Result<(), TransactionValidityError> {
+		Ok(())
+	}
+
+	fn validate(
+		&self,
+		who: &Self::AccountId,
+		call: &Self::Call,
+		info: &DispatchInfoOf<Self::Call>,
+		len: usize,
+	) -> TransactionValidity {
+		self.inner().validate(who, call, info, len)
+	}
+
+	fn pre_dispatch(
+		self,
+		who: &Self::AccountId,
+		call: &Self::Call,
+		info: &DispatchInfoOf<Self::Call>,
+		len: usize,
+	) -> Result<Self::Pre, TransactionValidityError> {
+		let pre = self.inner().pre_dispatch(who, call, info, len)?;
+		TxTip::<T>::put(self.0.unique_saturated_into());
+		Ok(pre)
+	}
+
+	fn post_dispatch(
+		pre: Option<Self::Pre>,
+		info: &DispatchInfoOf<Self::Call>,
+		post_info: &PostDispatchInfoOf<Self::Call>,
+		len: usize,
+		result: &sp_runtime::DispatchResult,
+	) -> Result<(), TransactionValidityError> {
+		TxTip::<T>::kill();
+		pallet_transaction_payment::ChargeTransactionPayment::<T>::post_dispatch(
+			pre, info, post_info, len, result,
+		)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use frame_support::{
+		construct_runtime, derive_impl,
+		dispatch::{DispatchClass, Pays},
+		traits::ConstU8,
+		weights::{IdentityFee, Weight},
+	};
+	use pallet_transaction_payment::CurrencyAdapter;
+	use sp_runtime::BuildStorage;
+
+	type Block = frame_system::mocking::MockBlock<Test>;
+
+	construct_runtime!(
+		pub enum Test {
+			System: frame_system,
+			Balances: pallet_balances,
+			TransactionPayment: pallet_transaction_payment,
+			Context: crate,
+		}
+	);
+
+	#[derive_impl(frame_system::config_preludes::TestDefaultConfig as frame_system::DefaultConfig)]
+	impl frame_system::Config for Test {
+		type Block = Block;
+		type AccountData = pallet_balances::AccountData<u64>;
+	}
+
+	#[derive_impl(pallet_balances::config_preludes::TestDefaultConfig as pallet_balances::DefaultConfig)]
+	impl pallet_balances::Config for Test {
+		type AccountStore = System;
+	}
+
+	impl pallet_transaction_payment::Config for Test {
+		type RuntimeEvent = RuntimeEvent;
+		type OnChargeTransaction = CurrencyAdapter<Balances, ()>;
+		type OperationalFeeMultiplier = ConstU8<5>;
+		type WeightToFee = IdentityFee<u64>;
+		type LengthToFee = IdentityFee<u64>;
+		type FeeMultiplierUpdate = ();
+	}
+
+	impl Config for Test {
+		type ChainId = GenesisHash<Test>;
+		type RelayChain = ();
+	}
+
+	const ALICE: u64 = 1;
+	const BALANCE: u64 = 1_000_000;
+	const LEN: usize = 10;
+
+	fn new_test_ext() -> sp_io::TestExternalities {
+		let mut storage = frame_system::GenesisConfig::<Test>::default().build_storage().unwrap();
+		pallet_balances::GenesisConfig::<Test> { balances: vec![(ALICE, BALANCE)] }
+			.assimilate_storage(&mut storage)
+			.unwrap();
+		storage.into()
+	}
+
+	fn call() -> RuntimeCall {
+		RuntimeCall::System(frame_system::Call::remark { remark: vec![] })
+	}
+
+	fn info() -> DispatchInfo {
+		DispatchInfo {
+			weight: Weight::from_parts(1_000, 0),
+			class: DispatchClass::Normal,
+			pays_fee: Pays::Yes,
+		}
+	}
+
+	#[test]
+	fn tx_origin_is_set_during_dispatch() {
+		new_test_ext().execute_with(|| {
+			let ext = RecordTxOrigin::<Test>::new();
+			let pre = ext.pre_dispatch(&ALICE, &call(), &info(), LEN).unwrap();
+			assert_eq!(Context::tx_origin(), Some(ALICE));
+
+			let post_info = PostDispatchInfo::default();
+			RecordTxOrigin::<Test>::post_dispatch(Some(pre), &info(), &post_info, LEN, &Ok(()))
+				.unwrap();
+			assert_eq!(Context::tx_origin(), None);
+		});
+	}
+
+	#[test]
+	fn tx_tip_is_set_during_dispatch() {
+		new_test_ext().execute_with(|| {
+			let ext = ChargeTransactionPayment::<Test>::from(7);
+			let pre = ext.pre_dispatch(&ALICE, &call(), &info(), LEN).unwrap();
+			assert_eq!(Context::tx_tip(), 7);
+
+			let post_info = PostDispatchInfo::default();
+			ChargeTransactionPayment::<Test>::post_dispatch(
+				Some(pre),
+				&info(),
+				&post_info,
+				LEN,
+				&Ok(()),
+			)
+			.unwrap();
+			assert_eq!(Context::tx_tip(), 0);
+		});
+	}
+
+	#[test]
+	fn encoding_matches_transaction_payment() {
+		type Original = pallet_transaction_payment::ChargeTransactionPayment<Test>;
+
+		assert_eq!(
+			<ChargeTransactionPayment<Test> as SignedExtension>::IDENTIFIER,
+			<Original as SignedExtension>::IDENTIFIER,
+		);
+		for tip in [0, 1, 63, 64, 1 << 30, u64::MAX] {
+			let encoded = ChargeTransactionPayment::<Test>::from(tip).encode();
+			assert_eq!(encoded, Original::from(tip).encode());
+			assert_eq!(
+				ChargeTransactionPayment::<Test>::decode(&mut &encoded[..]).unwrap(),
+				ChargeTransactionPayment::<Test>::from(tip),
+			);
+		}
+		assert!(RecordTxOrigin::<Test>::new().encode().is_empty());
+	}
+
+	#[test]
+	fn charges_fee_and_refunds_unused_weight() {
+		new_test_ext().execute_with(|| {
+			let tip = 5;
+			let fee = TransactionPayment::compute_fee(LEN as u32, &info(), tip);
+			let pre = ChargeTransactionPayment::<Test>::from(tip)
+				.pre_dispatch(&ALICE, &call(), &info(), LEN)
+				.unwrap();
+			assert_eq!(Balances::free_balance(ALICE), BALANCE - fee);
+
+			let post_info = PostDispatchInfo {
+				actual_weight: Some(Weight::from_parts(400, 0)),
+				pays_fee: Pays::Yes,
+			};
+			let actual_fee =
+				TransactionPayment::compute_actual_fee(LEN as u32, &info(), &post_info, tip);
+			assert!(actual_fee < fee);
+			ChargeTransactionPayment::<Test>::post_dispatch(
+				Some(pre),
+				&info(),
+				&post_info,
+				LEN,
+				&Ok(()),
+			)
+			.unwrap();
+			assert_eq!(Balances::free_balance(ALICE), BALANCE - actual_fee);
+		});
+	}
+
+	#[test]
+	fn rejects_unpayable_transactions() {
+		new_test_ext().execute_with(|| {
+			let ext = ChargeTransactionPayment::<Test>::from(BALANCE);
+			assert!(ext.clone().validate(&ALICE, &call(), &info(), LEN).is_err());
+			assert!(ext.pre_dispatch(&ALICE, &call(), &info(), LEN).is_err());
+			assert_eq!(Context::tx_tip(), 0);
+			assert_eq!(Balances::free_balance(ALICE), BALANCE);
+		});
+	}
+}
@@ -0,0 +1,463 @@
+//! # Contracts Limits Pallet
+//!
+//! The limits of `pallet-contracts` that differ between production chains, kept in storage so one
+//! runtime can imitate several chains. Genesis or the admin origin (sudo on the contracts node)
+//! sets them, and the runtime passes the getters of this pallet, e.g. [`MaxCodeLen`], to
+//! `pallet-contracts` and reads [`Pallet::limits`] when building its `Schedule`.
+//!
+//! The depth of the call stack is not among them, as `pallet-contracts` takes it as the length of
+//! an array type, which is fixed when the runtime is compiled.
+//!
+//! Lowering `max_code_len` below the size of uploaded code would make that code fail to decode, so
+//! the contracts using it could no longer be called. Only genesis may lower it, the admin origin
+//! may only raise it.
+//!
+//! The deposits only apply to storage that is added or removed after they were set. The deposits
+//! already held for the storage of existing contracts stay as they are. The limits are best set
+//! at genesis.
+//!
+//! A runtime that imitates one chain only sets [`Config::StrictLimits`] to its limits, so no
+//! limit can be set that is more lax.
+//!
+//! The storage reads of the getters are not charged to contracts.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::{Decode, Encode, MaxEncodedLen};
+use core::marker::PhantomData;
+use frame_support::traits::Get;
+use scale_info::TypeInfo;
+use sp_runtime::RuntimeDebug;
+
+pub use pallet::*;
+
+/// The stack size `pallet-contracts` reserves for each frame of the call stack.
+const MAX_STACK_SIZE: u32 = 1024 * 1024;
+
+/// The debug buffer `pallet-contracts` requires to be larger than, to hold an error message.
+const MIN_DEBUG_BUF_SIZE: u32 = 256;
+
+/// The limits of `pallet-contracts` kept in storage.
+#[derive(Clone, Encode, Decode, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct Limits<Balance> {
+	/// The largest code that can be uploaded, in bytes.
+	pub max_code_len: u32,
+	/// The memory available to the contracts of a call stack, `Schedule::limits.runtime_memory`.
+	pub runtime_memory: u32,
+	/// The largest debug buffer of a dry run, in bytes.
+	pub max_debug_buffer_len: u32,
+	/// The deposit for each storage item.
+	pub deposit_per_item: Balance,
+	/// The deposit for each byte of storage.
+	pub deposit_per_byte: Balance,
+	/// The storage deposit limit of dry runs without one.
+	pub default_deposit_limit: Balance,
+}
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::*;
+	use frame_support::pallet_prelude::*;
+	use frame_system::pallet_prelude::*;
+	use sp_runtime::traits::{MaybeSerializeDeserialize, Member, Zero};
+
+	#[pallet::pallet]
+	pub struct Pallet<T>(_);
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		/// The overarching event type.
+		type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
+
+		/// The origin which may set the limits.
+		type AdminOrigin: EnsureOrigin<Self::RuntimeOrigin>;
+
+		/// The balance type of the deposits.
+		type Balance: Parameter
+			+ Member
+			+ MaxEncodedLen
+			+ Copy
+			+ MaybeSerializeDeserialize
+			+ Zero
+			+ PartialOrd;
+
+		/// The limits until genesis or the admin origin sets others.
+		type DefaultLimits: Get<Limits<Self::Balance>>;
+
+		/// The length of the `CallStack` of `pallet-contracts`.
+		type CallStackDepth: Get<u32>;
+
+		/// The largest memory of a contract, `Schedule::limits.max_memory_size()`.
+		type MaxMemorySize: Get<u32>;
+
+		/// The limits no limit may be more lax than, if any: code, memory, debug buffer and
+		/// default deposit limit may not be larger, the deposits not smaller.
+		type StrictLimits: Get<Option<Limits<Self::Balance>>>;
+	}
+
+	/// The current limits.
+	#[pallet::storage]
+	pub type CurrentLimits<T: Config> =
+		StorageValue<_, Limits<T::Balance>, ValueQuery, T::DefaultLimits>;
+
+	/// Each limit that is set replaces the default one.
+	#[pallet::genesis_config]
+	#[derive(frame_support::DefaultNoBound)]
+	pub struct GenesisConfig<T: Config> {
+		pub max_code_len: Option<u32>,
+		pub runtime_memory: Option<u32>,
+		pub max_debug_buffer_len: Option<u32>,
+		pub deposit_per_item: Option<T::Balance>,
+		pub deposit_per_byte: Option<T::Balance>,
+		pub default_deposit_limit: Option<T::Balance>,
+		#[serde(skip)]
+		pub _config: PhantomData<T>,
+	}
+
+	#[pallet::genesis_build]
+	impl<T: Config> BuildGenesisConfig for GenesisConfig<T> {
+		fn build(&self) {
+			let default = T::DefaultLimits::get();
+			let limits = Limits {
+				max_code_len: self.max_code_len.unwrap_or(default.max_code_len),
+				runtime_memory: self.runtime_memory.unwrap_or(default.runtime_memory),
+				max_debug_buffer_len: self
+					.max_debug_buffer_len
+					.unwrap_or(default.max_debug_buffer_len),
+				deposit_per_item: self.deposit_per_item.unwrap_or(default.deposit_per_item),
+				deposit_per_byte: self.deposit_per_byte.unwrap_or(default.deposit_per_byte),
+				default_deposit_limit: self
+					.default_deposit_limit
+					.unwrap_or(default.default_deposit_limit),
+			};
+			Pallet::<T>::check(&limits).expect("the genesis limits of contracts are not valid");
+			CurrentLimits::<T>::put(limits);
+		}
+	}
+
+	#[pallet::event]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// The limits were set.
+		LimitsSet { limits: Limits<T::Balance> },
+	}
+
+	#[pallet::error]
+	pub enum Error<T> {
+		/// A call stack of contracts of `max_code_len` would not fit into `runtime_memory`.
+		CodeTooLargeForMemory,
+		/// `max_debug_buffer_len` is too small to hold an error message.
+		DebugBufferTooSmall,
+		/// `default_deposit_limit` is zero, which fails every dry run that adds storage.
+		ZeroDefaultDepositLimit,
+		/// `max_code_len` is lower than the current one, code that was uploaded may no longer
+		/// decode.
+		MaxCodeLenLowered,
+		/// A limit is more lax than the one in `StrictLimits`.
+		LaxerThanStrictLimits,
+	}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Set the limits of `pallet-contracts`.
+		///
+		/// `max_code_len` may not be lowered. New deposits do not change the deposits already
+		/// held for existing storage.
+		#[pallet::call_index(0)]
+		#[pallet::weight(T::DbWeight::get().reads_writes(1, 1))]
+		pub fn set_limits(origin: OriginFor<T>, limits: Limits<T::Balance>) -> DispatchResult {
+			T::AdminOrigin::ensure_origin(origin)?;
+			Self::check(&limits)?;
+			ensure!(
+				limits.max_code_len >= CurrentLimits::<T>::get().max_code_len,
+				Error::<T>::MaxCodeLenLowered
+			);
+			CurrentLimits::<T>::put(&limits);
+			Self::deposit_event(Event::LimitsSet { limits });
+			Ok(())
+		}
+	}
+
+	impl<T: Config> Pallet<T> {
+		/// The current limits.
+		pub fn limits() -> Limits<T::Balance> {
+			CurrentLimits::<T>::get()
+		}
+
+		/// Check the bounds `pallet-contracts` checks in its integrity test:
+		///
+		/// - Every frame of a call stack of the maximum depth may hold code of `max_code_len`,
+		///   decoded to 17 times its size and with 4 copies, its memory and its stack, in half of
+		///   `runtime_memory`.
+		/// - `max_debug_buffer_len` is larger than [`MIN_DEBUG_BUF_SIZE`].
+		///
+		/// A `default_deposit_limit` of zero is rejected as well, and so is any limit more lax than
+		/// the one in `StrictLimits`.
+		fn check(limits: &Limits<T::Balance>) -> Result<(), Error<T>> {
+			let code_len_limit = (limits.runtime_memory / 2 / (T::CallStackDepth::get() + 1))
+				.saturating_sub(T::MaxMemorySize::get())
+				.saturating_sub(MAX_STACK_SIZE) /
+				(17 * 4);
+			ensure!(limits.max_code_len < code_len_limit, Error::<T>::CodeTooLargeForMemory);
+			ensure!(
+				limits.max_debug_buffer_len > MIN_DEBUG_BUF_SIZE,
+				Error::<T>::DebugBufferTooSmall
+			);
+			ensure!(!limits.default_deposit_limit.is_zero(), Error::<T>::ZeroDefaultDepositLimit);
+			if let Some(strict) = T::StrictLimits::get() {
+				ensure!(
+					limits.max_code_len <= strict.max_code_len &&
+						limits.runtime_memory <= strict.runtime_memory &&
+						limits.max_debug_buffer_len <= strict.max_debug_buffer_len &&
+						limits.deposit_per_item >= strict.deposit_per_item &&
+						limits.deposit_per_byte >= strict.deposit_per_byte &&
+						limits.default_deposit_limit <= strict.default_deposit_limit,
+					Error::<T>::LaxerThanStrictLimits
+				);
+			}
+			Ok(())
+		}
+	}
+}
+
+macro_rules! limit_getter {
+	($(#[$doc:meta])* $name:ident: $field:ident -> $ty:ty) => {
+		$(#[$doc])*
+		pub struct $name<T>(PhantomData<T>);
+
+		impl<T: Config> Get<$ty> for $name<T> {
+			fn get() -> $ty {
+				CurrentLimits::<T>::get().$field
+			}
+		}
+	};
+}
+
+limit_getter!(
+	/// The `MaxCodeLen` of `pallet-contracts`.
+	MaxCodeLen: max_code_len -> u32
+);
+limit_getter!(
+	/// The `MaxDebugBufferLen` of `pallet-contracts`.
+	MaxDebugBufferLen: max_debug_buffer_len -> u32
+);
+limit_getter!(
+	/// The `DepositPerItem` of `pallet-contracts`.
+	DepositPerItem: deposit_per_item -> T::Balance
+);
+limit_getter!(
+	/// The `DepositPerByte` of `pallet-contracts`.
+	DepositPerByte: deposit_per_byte -> T::Balance
+);
+limit_getter!(
+	/// The `DefaultDepositLimit` of `pallet-contracts`.
+	DefaultDepositLimit: default_deposit_limit -> T::Balance
+);
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use frame_support::{
+		assert_noop, assert_ok, construct_runtime, derive_impl, parameter_types, traits::ConstU32,
+	};
+	use frame_system::EnsureRoot;
+	use sp_runtime::{BuildStorage, DispatchError};
+
+	type Block = frame_system::mocking::MockBlock<Test>;
+
+	construct_runtime!(
+		pub enum Test {
+			System: frame_system,
+			ContractsLimits: crate,
+		}
+	);
+
+	#[derive_impl(frame_system::config_preludes::TestDefaultConfig as frame_system::DefaultConfig)]
+	impl frame_system::Config for Test {
+		type Block = Block;
+	}
+
+	parameter_types! {
+		pub DefaultLimits: Limits<u64> = Limits {
+			max_code_len: 128 * 1024,
+			runtime_memory: 128 * 1024 * 1024,
+			max_debug_buffer_len: 2 * 1024 * 1024,
+			deposit_per_item: 10,
+			deposit_per_byte: 1,
+			default_deposit_limit: 1_000_000,
+		};
+		pub static StrictLimits: Option<Limits<u64>> = None;
+	}
+
+	impl Config for Test {
+		type RuntimeEvent = RuntimeEvent;
+		type AdminOrigin = EnsureRoot<u64>;
+		type Balance = u64;
+		type DefaultLimits = DefaultLimits;
+		type CallStackDepth = ConstU32<5>;
+		type MaxMemorySize = ConstU32<{ 16 * 64 * 1024 }>;
+		type StrictLimits = StrictLimits;
+	}
+
+	/// The smallest `max_code_len` that is too large for the default `runtime_memory`:
+	/// `(128 MiB / 2 / 6 - 1 MiB - 1 MiB) / 68`.
+	const CODE_LEN_LIMIT: u32 = 133_642;
+
+	fn new_test_ext(genesis: GenesisConfig<Test>) -> sp_io::TestExternalities {
+		let storage =
+			RuntimeGenesisConfig { system: Default::default(), contracts_limits: genesis }
+				.build_storage()
+				.unwrap();
+		let mut ext = sp_io::TestExternalities::from(storage);
+		ext.execute_with(|| System::set_block_number(1));
+		ext
+	}
+
+	#[test]
+	fn defaults_apply_without_genesis_overrides() {
+		new_test_ext(Default::default()).execute_with(|| {
+			assert_eq!(ContractsLimits::limits(), DefaultLimits::get());
+			assert_eq!(MaxCodeLen::<Test>::get(), 128 * 1024);
+			assert_eq!(MaxDebugBufferLen::<Test>::get(), 2 * 1024 * 1024);
+			assert_eq!(DepositPerItem::<Test>::get(), 10);
+			assert_eq!(DepositPerByte::<Test>::get(), 1);
+			assert_eq!(DefaultDepositLimit::<Test>::get(), 1_000_000);
+		});
+	}
+
+	#[test]
+	fn genesis_overrides_single_limits() {
+		let genesis = GenesisConfig {
+			max_code_len: Some(64 * 1024),
+			deposit_per_byte: Some(2),
+			..Default::default()
+		};
+		new_test_ext(genesis).execute_with(|| {
+			assert_eq!(
+				ContractsLimits::limits(),
+				Limits { max_code_len: 64 * 1024, deposit_per_byte: 2, ..DefaultLimits::get() }
+			);
+			assert_eq!(MaxCodeLen::<Test>::get(), 64 * 1024);
+			assert_eq!(DepositPerByte::<Test>::get(), 2);
+		});
+	}
+
+	#[test]
+	#[should_panic(expected = "the genesis limits of contracts are not valid")]
+	fn genesis_rejects_invalid_limits() {
+		new_test_ext(GenesisConfig { max_code_len: Some(CODE_LEN_LIMIT), ..Default::default() });
+	}
+
+	#[test]
+	fn set_limits_replaces_all_limits() {
+		new_test_ext(Default::default()).execute_with(|| {
+			let limits = Limits {
+				max_code_len: 256 * 1024,
+				runtime_memory: 1024 * 1024 * 1024,
+				max_debug_buffer_len: 1024,
+				deposit_per_item: 0,
+				deposit_per_byte: 0,
+				default_deposit_limit: 1,
+			};
+			assert_ok!(ContractsLimits::set_limits(RuntimeOrigin::root(), limits.clone()));
+			System::assert_last_event(Event::<Test>::LimitsSet { limits: limits.clone() }.into());
+			assert_eq!(ContractsLimits::limits(), limits);
+			assert_eq!(MaxCodeLen::<Test>::get(), 256 * 1024);
+			assert_eq!(DefaultDepositLimit::<Test>::get(), 1);
+		});
+	}
+
+	#[test]
+	fn only_admin_sets_limits() {
+		new_test_ext(Default::default()).execute_with(|| {
+			assert_noop!(
+				ContractsLimits::set_limits(RuntimeOrigin::signed(1), DefaultLimits::get()),
+				DispatchError::BadOrigin
+			);
+		});
+	}
+
+	#[test]
+	fn set_limits_checks_the_bounds_of_pallet_contracts() {
+		new_test_ext(Default::default()).execute_with(|| {
+			let set = |limits| ContractsLimits::set_limits(RuntimeOrigin::root(), limits);
+
+			assert_noop!(
+				set(Limits { max_code_len: CODE_LEN_LIMIT, ..DefaultLimits::get() }),
+				Error::<Test>::CodeTooLargeForMemory
+			);
+			// Less memory lowers the limit of the code.
+			assert_noop!(
+				set(Limits { runtime_memory: 64 * 1024 * 1024, ..DefaultLimits::get() }),
+				Error::<Test>::CodeTooLargeForMemory
+			);
+
+			assert_ok!(set(Limits {
+				max_debug_buffer_len: MIN_DEBUG_BUF_SIZE + 1,
+				..DefaultLimits::get()
+			}));
+			assert_noop!(
+				set(Limits { max_debug_buffer_len: MIN_DEBUG_BUF_SIZE, ..DefaultLimits::get() }),
+				Error::<Test>::DebugBufferTooSmall
+			);
+
+			assert_noop!(
+				set(Limits { default_deposit_limit: 0, ..DefaultLimits::get() }),
+				Error::<Test>::ZeroDefaultDepositLimit
+			);
+
+			assert_ok!(set(Limits { max_code_len: CODE_LEN_LIMIT - 1, ..DefaultLimits::get() }));
+		});
+	}
+
+	#[test]
+	fn max_code_len_is_not_lowered() {
+		new_test_ext(Default::default()).execute_with(|| {
+			let set = |limits| ContractsLimits::set_limits(RuntimeOrigin::root(), limits);
+
+			assert_noop!(
+				set(Limits { max_code_len: 64 * 1024, ..DefaultLimits::get() }),
+				Error::<Test>::MaxCodeLenLowered
+			);
+			assert_ok!(set(Limits { max_code_len: 256 * 1024, ..DefaultLimits::get() }));
+			assert_noop!(set(DefaultLimits::get()), Error::<Test>::MaxCodeLenLowered);
+			// The other limits may still change.
+			assert_ok!(set(Limits {
+				max_code_len: 256 * 1024,
+				deposit_per_byte: 2,
+				..DefaultLimits::get()
+			}));
+			assert_eq!(DepositPerByte::<Test>::get(), 2);
+		});
+	}
+
+	#[test]
+	fn strict_limits_are_not_relaxed() {
+		StrictLimits::set(Some(DefaultLimits::get()));
+		new_test_ext(Default::default()).execute_with(|| {
+			let set = |limits| ContractsLimits::set_limits(RuntimeOrigin::root(), limits);
+
+			for limits in [
+				Limits { max_code_len: 128 * 1024 + 1, ..DefaultLimits::get() },
+				Limits { runtime_memory: 256 * 1024 * 1024, ..DefaultLimits::get() },
+				Limits { max_debug_buffer_len: 4 * 1024 * 1024, ..DefaultLimits::get() },
+				Limits { deposit_per_item: 9, ..DefaultLimits::get() },
+				Limits { deposit_per_byte: 0, ..DefaultLimits::get() },
+				Limits { default_deposit_limit: 1_000_001, ..DefaultLimits::get() },
+			] {
+				assert_noop!(set(limits), Error::<Test>::LaxerThanStrictLimits);
+			}
+			// Stricter limits may be set.
+			let limits = Limits {
+				max_debug_buffer_len: 1024,
+				deposit_per_item: 20,
+				deposit_per_byte: 2,
+				default_deposit_limit: 1_000,
+				..DefaultLimits::get()
+			};
+			assert_ok!(set(limits.clone()));
+			assert_eq!(ContractsLimits::limits(), limits);
+		});
+		StrictLimits::set(None);
+	}
+}
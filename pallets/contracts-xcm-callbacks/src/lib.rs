@@ -0,0 +1,850 @@
+//! # Contracts XCM Callbacks Pallet
+//!
+//! Responses to XCM queries end up in `pallet-xcm`, where a contract can only poll for them. This
+//! pallet registers queries of contracts as notify queries of `pallet-xcm`: when the response
+//! arrives, a selector of the contract is called with the outcome, within the gas limit the
+//! contract reserved when registering.
+//!
+//! Responses are executed without fees, so the price of the gas limit is held from the balance of
+//! the contract when it registers the query. The gas the callback used is burned like a fee when
+//! it is called, the rest is released. A query that timed out without a response can be removed by
+//! anyone with [`Pallet::remove_expired`], which releases all of it.
+//!
+//! The callback is called with the contract itself as caller, which no other account can fake.
+//! Its input is the selector followed by the SCALE encoded `(query_id: u64, status: u8)`, where the
+//! status is 1 if the queried program was executed successfully and 2 otherwise.
+//!
+//! Contracts that rather poll for the response register the query with
+//! [`Pallet::new_polled_query`]. Only the contract that registered such a query can take its
+//! response, see [`Pallet::take_response`].
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::{Decode, Encode, MaxEncodedLen};
+use frame_support::{traits::fungible::Inspect, weights::Weight};
+use scale_info::TypeInfo;
+use sp_runtime::RuntimeDebug;
+
+pub use pallet::*;
+
+type BalanceOf<T> = <<T as pallet_contracts::Config>::Currency as Inspect<
+	<T as frame_system::Config>::AccountId,
+>>::Balance;
+
+/// The callback of a query.
+#[derive(Clone, Encode, Decode, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct Callback<AccountId, Balance, BlockNumber> {
+	/// The contract that registered the query.
+	pub contract: AccountId,
+	/// The selector of the contract's callback.
+	pub selector: [u8; 4],
+	/// The gas limit of the callback.
+	pub gas_limit: Weight,
+	/// The price of `gas_limit`, held from the balance of `contract`.
+	pub deposit: Balance,
+	/// The block after which the query can be removed with [`Pallet::remove_expired`].
+	pub timeout: BlockNumber,
+}
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::*;
+	use frame_support::{
+		pallet_prelude::*,
+		traits::{
+			fungible::MutateHold,
+			tokens::{Fortitude, Precision},
+		},
+	};
+	use frame_system::pallet_prelude::*;
+	use pallet_contracts::{weights::WeightInfo, CollectEvents, DebugInfo, Determinism};
+	use pallet_xcm::WeightInfo as XcmWeightInfo;
+	use sp_runtime::traits::{Convert, Saturating, Zero};
+	use sp_std::vec::Vec;
+	use xcm::latest::{Location, QueryId, Response};
+	use xcm_executor::traits::{QueryHandler, QueryResponseStatus};
+
+	type CallbackOf<T> =
+		Callback<<T as frame_system::Config>::AccountId, BalanceOf<T>, BlockNumberFor<T>>;
+
+	#[pallet::pallet]
+	pub struct Pallet<T>(_);
+
+	/// A reason for the pallet to hold funds.
+	#[pallet::composite_enum]
+	pub enum HoldReason {
+		/// The price of the gas limit of a callback.
+		CallbackGas,
+	}
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config + pallet_contracts::Config + pallet_xcm::Config {
+		/// The overarching event type.
+		type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
+
+		/// The overarching call type.
+		type RuntimeCall: From<Call<Self>> + Into<<Self as pallet_xcm::Config>::RuntimeCall>;
+
+		/// The overarching hold reason, which `pallet-contracts` holds its deposits with.
+		type RuntimeHoldReason: From<HoldReason>
+			+ Into<<Self as pallet_contracts::Config>::RuntimeHoldReason>;
+
+		/// The origin `pallet-xcm` notifies about responses with.
+		type ResponseOrigin: EnsureOrigin<
+			<Self as frame_system::Config>::RuntimeOrigin,
+			Success = Location,
+		>;
+
+		/// The largest gas limit a contract may reserve for a callback.
+		#[pallet::constant]
+		type MaxCallbackGas: Get<Weight>;
+	}
+
+	/// The callbacks of pending queries.
+	#[pallet::storage]
+	pub type Callbacks<T: Config> =
+		StorageMap<_, Twox64Concat, QueryId, CallbackOf<T>, OptionQuery>;
+
+	/// The contracts that registered the pending queries polled with [`Pallet::take_response`],
+	/// and the timeouts of the queries.
+	#[pallet::storage]
+	pub type QueryOwners<T: Config> =
+		StorageMap<_, Twox64Concat, QueryId, (T::AccountId, BlockNumberFor<T>), OptionQuery>;
+
+	#[pallet::event]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// The callback of a query was called.
+		CallbackCalled { query_id: QueryId, contract: T::AccountId, success: bool },
+		/// A query timed out without a response and was removed.
+		QueryExpired { query_id: QueryId, contract: T::AccountId },
+	}
+
+	#[pallet::error]
+	pub enum Error<T> {
+		/// The gas limit of the callback exceeds `MaxCallbackGas`.
+		GasLimitTooHigh,
+		/// The contract cannot pay for the gas limit of the callback.
+		InsufficientBalance,
+		/// There is no pending query with this id.
+		UnknownQuery,
+		/// The query did not time out yet.
+		NotExpired,
+	}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Called by `pallet-xcm` with the response to a query registered by [`Self::new_query`].
+		#[pallet::call_index(0)]
+		#[pallet::weight(Pallet::<T>::response_weight_limit())]
+		pub fn on_response(
+			origin: OriginFor<T>,
+			query_id: QueryId,
+			response: Response,
+		) -> DispatchResultWithPostInfo {
+			T::ResponseOrigin::ensure_origin(origin)?;
+			let base = T::DbWeight::get().reads_writes(1, 1);
+			let Some(Callback { contract, selector, gas_limit, deposit, .. }) =
+				Callbacks::<T>::take(query_id)
+			else {
+				return Ok(Some(base).into())
+			};
+
+			let status: u8 = match response {
+				Response::ExecutionResult(None) => 1,
+				_ => 2,
+			};
+			let mut data = Vec::from(selector);
+			(query_id, status).encode_to(&mut data);
+
+			let result = pallet_contracts::Pallet::<T>::bare_call(
+				contract.clone(),
+				contract.clone(),
+				Zero::zero(),
+				gas_limit,
+				None,
+				data,
+				DebugInfo::Skip,
+				CollectEvents::Skip,
+				Determinism::Enforced,
+			);
+			let success = result.result.map_or(false, |output| !output.did_revert());
+			// The gas used is paid like a fee.
+			let used = <T as pallet_contracts::Config>::WeightPrice::convert(result.gas_consumed);
+			Self::settle(&contract, deposit, used.min(deposit));
+			Self::deposit_event(Event::CallbackCalled { query_id, contract, success });
+
+			let call = <T as pallet_contracts::Config>::WeightInfo::call();
+			Ok(Some(
+				base.saturating_add(Self::settle_weight())
+					.saturating_add(call)
+					.saturating_add(result.gas_consumed),
+			)
+			.into())
+		}
+
+		/// Remove a query that timed out without a response, and release the price of the gas
+		/// limit of its callback. Anyone may remove it.
+		///
+		/// A response that arrives afterwards is ignored.
+		#[pallet::call_index(1)]
+		#[pallet::weight(Pallet::<T>::remove_expired_weight())]
+		pub fn remove_expired(origin: OriginFor<T>, query_id: QueryId) -> DispatchResult {
+			ensure_signed(origin)?;
+			let now = frame_system::Pallet::<T>::block_number();
+			let contract = if let Some(callback) = Callbacks::<T>::get(query_id) {
+				ensure!(now > callback.timeout, Error::<T>::NotExpired);
+				Callbacks::<T>::remove(query_id);
+				Self::settle(&callback.contract, callback.deposit, Zero::zero());
+				callback.contract
+			} else {
+				let (contract, timeout) =
+					QueryOwners::<T>::get(query_id).ok_or(Error::<T>::UnknownQuery)?;
+				ensure!(now > timeout, Error::<T>::NotExpired);
+				QueryOwners::<T>::remove(query_id);
+				contract
+			};
+			Self::deposit_event(Event::QueryExpired { query_id, contract });
+			Ok(())
+		}
+	}
+
+	impl<T: Config> Pallet<T> {
+		/// Register a query for the response of `responder` to a message of `contract`.
+		///
+		/// The response is passed to `selector` of `contract`, with up to `gas_limit`. `querier` is
+		/// the location of `contract`, as reported by `responder`.
+		///
+		/// Responses are executed without fees, so the price of `gas_limit` is held from the
+		/// balance of `contract` until the callback was called or the query was removed after
+		/// `timeout`.
+		pub fn new_query(
+			contract: T::AccountId,
+			querier: Location,
+			responder: Location,
+			timeout: BlockNumberFor<T>,
+			selector: [u8; 4],
+			gas_limit: Weight,
+		) -> Result<QueryId, DispatchError> {
+			ensure!(gas_limit.all_lte(T::MaxCallbackGas::get()), Error::<T>::GasLimitTooHigh);
+			let deposit = <T as pallet_contracts::Config>::WeightPrice::convert(gas_limit);
+			<T as pallet_contracts::Config>::Currency::hold(
+				&Self::hold_reason(),
+				&contract,
+				deposit,
+			)
+			.map_err(|_| Error::<T>::InsufficientBalance)?;
+			// `pallet-xcm` replaces the arguments by the actual query id and response.
+			let notify = Call::<T>::on_response { query_id: 0, response: Response::Null };
+			let query_id = pallet_xcm::Pallet::<T>::new_notify_query(
+				responder,
+				<T as Config>::RuntimeCall::from(notify),
+				timeout,
+				querier,
+			);
+			Callbacks::<T>::insert(
+				query_id,
+				Callback { contract, selector, gas_limit, deposit, timeout },
+			);
+			Ok(query_id)
+		}
+
+		/// Register a query for the response of `responder` to a message of `contract`, which
+		/// `contract` polls with [`Self::take_response`]. `querier` is the location of `contract`,
+		/// as reported by `responder`.
+		pub fn new_polled_query(
+			contract: T::AccountId,
+			querier: Location,
+			responder: Location,
+			timeout: BlockNumberFor<T>,
+		) -> QueryId {
+			let query_id =
+				<pallet_xcm::Pallet<T> as QueryHandler>::new_query(responder, timeout, querier);
+			QueryOwners::<T>::insert(query_id, (contract, timeout));
+			query_id
+		}
+
+		/// Take the response to `query_id`, `None` unless `contract` registered it with
+		/// [`Self::new_polled_query`].
+		pub fn take_response(
+			contract: &T::AccountId,
+			query_id: QueryId,
+		) -> Option<QueryResponseStatus<BlockNumberFor<T>>> {
+			if QueryOwners::<T>::get(query_id).map(|(owner, _)| owner).as_ref() != Some(contract) {
+				return None
+			}
+			let status = <pallet_xcm::Pallet<T> as QueryHandler>::take_response(query_id);
+			if !matches!(status, QueryResponseStatus::Pending { .. }) {
+				QueryOwners::<T>::remove(query_id);
+			}
+			Some(status)
+		}
+
+		/// The weight the response to `query_id` may use, for its `QueryResponseInfo`.
+		pub fn response_weight(query_id: QueryId) -> Weight {
+			if Callbacks::<T>::contains_key(query_id) {
+				Self::response_weight_limit()
+			} else {
+				Weight::zero()
+			}
+		}
+
+		/// The worst case weight of [`Self::on_response`].
+		pub fn response_weight_limit() -> Weight {
+			T::DbWeight::get()
+				.reads_writes(1, 1)
+				.saturating_add(Self::settle_weight())
+				.saturating_add(<T as pallet_contracts::Config>::WeightInfo::call())
+				.saturating_add(T::MaxCallbackGas::get())
+		}
+
+		/// The weight of [`Self::new_query`] and [`Self::new_polled_query`].
+		pub fn new_query_weight() -> Weight {
+			<T as pallet_xcm::Config>::WeightInfo::new_query()
+				.saturating_add(Self::settle_weight())
+				.saturating_add(T::DbWeight::get().writes(1))
+		}
+
+		/// The worst case weight of [`Self::remove_expired`].
+		pub fn remove_expired_weight() -> Weight {
+			T::DbWeight::get().reads_writes(2, 1).saturating_add(Self::settle_weight())
+		}
+
+		/// The worst case weight of [`Self::take_response`].
+		pub fn take_response_weight() -> Weight {
+			<T as pallet_xcm::Config>::WeightInfo::take_response()
+				.saturating_add(T::DbWeight::get().reads_writes(1, 1))
+		}
+
+		fn hold_reason() -> <T as pallet_contracts::Config>::RuntimeHoldReason {
+			<T as Config>::RuntimeHoldReason::from(HoldReason::CallbackGas).into()
+		}
+
+		/// Burn `used` of the `deposit` held from `contract` and release the rest.
+		fn settle(contract: &T::AccountId, deposit: BalanceOf<T>, used: BalanceOf<T>) {
+			let reason = Self::hold_reason();
+			let burned = <T as pallet_contracts::Config>::Currency::burn_held(
+				&reason,
+				contract,
+				used,
+				Precision::BestEffort,
+				Fortitude::Force,
+			)
+			.unwrap_or_default();
+			let _ = <T as pallet_contracts::Config>::Currency::release(
+				&reason,
+				contract,
+				deposit.saturating_sub(burned),
+				Precision::BestEffort,
+			);
+		}
+
+		/// The weight of holding, burning and releasing funds of a contract.
+		fn settle_weight() -> Weight {
+			T::DbWeight::get().reads_writes(2, 2)
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use frame_support::{
+		assert_noop, assert_ok, construct_runtime, derive_impl, parameter_types,
+		traits::{
+			fungible::InspectHold, ConstBool, ConstU32, ConstU64, Everything, Nothing, Randomness,
+		},
+	};
+	use frame_system::EnsureRoot;
+	use pallet_contracts::{weights::WeightInfo, Code, CollectEvents, DebugInfo};
+	use sp_runtime::{
+		testing::H256,
+		traits::{Convert, IdentityLookup},
+		AccountId32, BuildStorage, DispatchError, Perbill,
+	};
+	use xcm::latest::{
+		Error as XcmError, InteriorLocation, Junction, Location, NetworkId, QueryId, Response,
+		XcmContext,
+	};
+	use xcm_builder::{
+		AccountId32Aliases, AllowKnownQueryResponses, EnsureXcmOrigin, FixedWeightBounds,
+		FrameTransactionalProcessor, SignedToAccountId32,
+	};
+	use xcm_executor::{
+		traits::{OnResponse, QueryResponseStatus},
+		XcmExecutor,
+	};
+
+	type Block = frame_system::mocking::MockBlock<Test>;
+
+	construct_runtime!(
+		pub enum Test {
+			System: frame_system,
+			Balances: pallet_balances,
+			Timestamp: pallet_timestamp,
+			Contracts: pallet_contracts,
+			XcmPallet: pallet_xcm,
+			XcmCallbacks: crate,
+		}
+	);
+
+	#[derive_impl(frame_system::config_preludes::TestDefaultConfig as frame_system::DefaultConfig)]
+	impl frame_system::Config for Test {
+		type Block = Block;
+		type AccountId = AccountId32;
+		type Lookup = IdentityLookup<AccountId32>;
+		type AccountData = pallet_balances::AccountData<u64>;
+	}
+
+	#[derive_impl(pallet_balances::config_preludes::TestDefaultConfig as pallet_balances::DefaultConfig)]
+	impl pallet_balances::Config for Test {
+		type AccountStore = System;
+	}
+
+	impl pallet_timestamp::Config for Test {
+		type Moment = u64;
+		type OnTimestampSet = ();
+		type MinimumPeriod = ConstU64<1>;
+		type WeightInfo = ();
+	}
+
+	pub struct TestRandomness;
+
+	impl Randomness<H256, u64> for TestRandomness {
+		fn random(_subject: &[u8]) -> (H256, u64) {
+			(H256::zero(), 0)
+		}
+	}
+
+	/// A thousandth of the `ref_time`.
+	pub struct TestWeightPrice;
+
+	impl Convert<Weight, u64> for TestWeightPrice {
+		fn convert(weight: Weight) -> u64 {
+			weight.ref_time() / 1_000
+		}
+	}
+
+	parameter_types! {
+		pub Schedule: pallet_contracts::Schedule<Test> = Default::default();
+		pub const CodeHashLockupDepositPercent: Perbill = Perbill::from_percent(0);
+	}
+
+	impl pallet_contracts::Config for Test {
+		type Time = Timestamp;
+		type Randomness = TestRandomness;
+		type Currency = Balances;
+		type RuntimeEvent = RuntimeEvent;
+		type RuntimeCall = RuntimeCall;
+		type CallFilter = Nothing;
+		type DepositPerItem = ConstU64<1>;
+		type DepositPerByte = ConstU64<1>;
+		type CallStack = [pallet_contracts::Frame<Self>; 5];
+		type WeightPrice = TestWeightPrice;
+		type WeightInfo = ();
+		type ChainExtension = ();
+		type Schedule = Schedule;
+		type AddressGenerator = pallet_contracts::DefaultAddressGenerator;
+		type MaxCodeLen = ConstU32<{ 123 * 1024 }>;
+		type DefaultDepositLimit = ConstU64<10_000_000>;
+		type MaxStorageKeyLen = ConstU32<128>;
+		type MaxDebugBufferLen = ConstU32<{ 2 * 1024 * 1024 }>;
+		type UnsafeUnstableInterface = ConstBool<false>;
+		type CodeHashLockupDepositPercent = CodeHashLockupDepositPercent;
+		type MaxDelegateDependencies = ConstU32<32>;
+		type RuntimeHoldReason = RuntimeHoldReason;
+		type Environment = ();
+		type Debug = ();
+		type ApiVersion = ();
+		type Migrations = ();
+		type Xcm = ();
+	}
+
+	parameter_types! {
+		pub UniversalLocation: InteriorLocation = Junction::Parachain(2000).into();
+		pub UnitWeightCost: Weight = Weight::from_parts(1_000_000, 1_024);
+		pub const AnyNetwork: Option<NetworkId> = None;
+		pub MaxCallbackGas: Weight = Weight::from_parts(50_000_000_000, 512 * 1024);
+	}
+
+	type LocalOriginToLocation = SignedToAccountId32<RuntimeOrigin, AccountId32, AnyNetwork>;
+	type Weigher = FixedWeightBounds<UnitWeightCost, RuntimeCall, ConstU32<100>>;
+
+	pub struct XcmConfig;
+
+	impl xcm_executor::Config for XcmConfig {
+		type RuntimeCall = RuntimeCall;
+		type XcmSender = ();
+		type AssetTransactor = ();
+		type OriginConverter = ();
+		type IsReserve = ();
+		type IsTeleporter = ();
+		type UniversalLocation = UniversalLocation;
+		type Barrier = AllowKnownQueryResponses<XcmPallet>;
+		type Weigher = Weigher;
+		type Trader = ();
+		type ResponseHandler = XcmPallet;
+		type AssetTrap = XcmPallet;
+		type AssetClaims = XcmPallet;
+		type SubscriptionService = XcmPallet;
+		type PalletInstancesInfo = AllPalletsWithSystem;
+		type MaxAssetsIntoHolding = ConstU32<64>;
+		type AssetLocker = ();
+		type AssetExchanger = ();
+		type FeeManager = ();
+		type MessageExporter = ();
+		type UniversalAliases = Nothing;
+		type CallDispatcher = RuntimeCall;
+		type SafeCallFilter = Everything;
+		type Aliasers = Nothing;
+		type TransactionalProcessor = FrameTransactionalProcessor;
+	}
+
+	impl pallet_xcm::Config for Test {
+		type RuntimeEvent = RuntimeEvent;
+		type SendXcmOrigin = EnsureXcmOrigin<RuntimeOrigin, LocalOriginToLocation>;
+		type XcmRouter = ();
+		type ExecuteXcmOrigin = EnsureXcmOrigin<RuntimeOrigin, LocalOriginToLocation>;
+		type XcmExecuteFilter = Everything;
+		type XcmExecutor = XcmExecutor<XcmConfig>;
+		type XcmTeleportFilter = Everything;
+		type XcmReserveTransferFilter = Everything;
+		type Weigher = Weigher;
+		type UniversalLocation = UniversalLocation;
+		type RuntimeOrigin = RuntimeOrigin;
+		type RuntimeCall = RuntimeCall;
+		const VERSION_DISCOVERY_QUEUE_SIZE: u32 = 100;
+		type AdvertisedXcmVersion = pallet_xcm::CurrentXcmVersion;
+		type Currency = Balances;
+		type CurrencyMatcher = ();
+		type TrustedLockers = ();
+		type SovereignAccountOf = AccountId32Aliases<AnyNetwork, AccountId32>;
+		type MaxLockers = ConstU32<8>;
+		type WeightInfo = pallet_xcm::TestWeightInfo;
+		type AdminOrigin = EnsureRoot<AccountId32>;
+		type MaxRemoteLockConsumers = ConstU32<0>;
+		type RemoteLockConsumerIdentifier = ();
+	}
+
+	impl Config for Test {
+		type RuntimeEvent = RuntimeEvent;
+		type RuntimeCall = RuntimeCall;
+		type RuntimeHoldReason = RuntimeHoldReason;
+		type ResponseOrigin = pallet_xcm::EnsureResponse<Everything>;
+		type MaxCallbackGas = MaxCallbackGas;
+	}
+
+	const ALICE: AccountId32 = AccountId32::new([1; 32]);
+	const BOB: AccountId32 = AccountId32::new([2; 32]);
+
+	fn new_test_ext() -> sp_io::TestExternalities {
+		let mut storage = frame_system::GenesisConfig::<Test>::default().build_storage().unwrap();
+		pallet_balances::GenesisConfig::<Test> { balances: vec![(ALICE, 1_000_000_000_000)] }
+			.assimilate_storage(&mut storage)
+			.unwrap();
+		let mut ext = sp_io::TestExternalities::new(storage);
+		ext.execute_with(|| System::set_block_number(1));
+		ext
+	}
+
+	/// The location of `contract`, as the sibling reports it.
+	fn querier(contract: &AccountId32) -> Location {
+		Location::new(0, [Junction::AccountId32 { network: None, id: contract.clone().into() }])
+	}
+
+	fn sibling() -> Location {
+		Location::new(1, [Junction::Parachain(1000)])
+	}
+
+	/// Emits its input, the selector and the outcome of the query, as event.
+	const CALLBACK_CONTRACT: &str = r#"
+		(module
+			(import "seal0" "seal_input" (func $seal_input (param i32 i32)))
+			(import "seal0" "seal_deposit_event" (func $seal_deposit_event (param i32 i32 i32 i32)))
+			(import "env" "memory" (memory 1 1))
+
+			;; [0, 4) the length of the input buffer, [4, 256) the input
+			(data (i32.const 0) "\fc")
+
+			(func (export "deploy"))
+
+			(func (export "call")
+				(call $seal_input (i32.const 4) (i32.const 0))
+				;; no topics
+				(call $seal_deposit_event
+					(i32.const 0)
+					(i32.const 0)
+					(i32.const 4)
+					(i32.load (i32.const 0))
+				)
+			)
+		)
+	"#;
+
+	const GAS_LIMIT: Weight = Weight::from_parts(100_000_000_000, 3 * 1024 * 1024);
+	const CALLBACK_GAS: Weight = Weight::from_parts(10_000_000_000, 256 * 1024);
+
+	/// Instantiate the callback contract with enough balance for a few callbacks.
+	fn instantiate_callback_contract() -> AccountId32 {
+		Contracts::bare_instantiate(
+			ALICE,
+			100_000_000,
+			GAS_LIMIT,
+			None,
+			Code::Upload(wat::parse_str(CALLBACK_CONTRACT).unwrap()),
+			vec![],
+			vec![],
+			DebugInfo::Skip,
+			CollectEvents::Skip,
+		)
+		.result
+		.unwrap()
+		.account_id
+	}
+
+	fn new_callback_query(contract: &AccountId32) -> QueryId {
+		XcmCallbacks::new_query(
+			contract.clone(),
+			querier(contract),
+			sibling(),
+			10,
+			[1, 2, 3, 4],
+			CALLBACK_GAS,
+		)
+		.unwrap()
+	}
+
+	/// The price of the gas limits of the callbacks of `contract`.
+	fn held(contract: &AccountId32) -> u64 {
+		Balances::balance_on_hold(&HoldReason::CallbackGas.into(), contract)
+	}
+
+	fn response_origin() -> RuntimeOrigin {
+		pallet_xcm::Origin::Response(sibling()).into()
+	}
+
+	/// The events emitted by `contract`.
+	fn emitted(contract: &AccountId32) -> Vec<Vec<u8>> {
+		System::events()
+			.into_iter()
+			.filter_map(|record| match record.event {
+				RuntimeEvent::Contracts(pallet_contracts::Event::ContractEmitted {
+					contract: emitter,
+					data,
+				}) if &emitter == contract => Some(data),
+				_ => None,
+			})
+			.collect()
+	}
+
+	/// Deliver `response` from the sibling to `pallet-xcm`, like the XCM executor does.
+	fn respond(query_id: QueryId, querier: &Location, response: Response) -> Weight {
+		<XcmPallet as OnResponse>::on_response(
+			&sibling(),
+			query_id,
+			Some(querier),
+			response,
+			XcmCallbacks::response_weight_limit(),
+			&XcmContext::with_message_id([0; 32]),
+		)
+	}
+
+	#[test]
+	fn only_the_registering_contract_takes_the_response() {
+		new_test_ext().execute_with(|| {
+			let query_id = XcmCallbacks::new_polled_query(ALICE, querier(&ALICE), sibling(), 10);
+			assert!(XcmCallbacks::take_response(&BOB, query_id).is_none());
+			assert!(matches!(
+				XcmCallbacks::take_response(&ALICE, query_id),
+				Some(QueryResponseStatus::Pending { .. })
+			));
+
+			respond(query_id, &querier(&ALICE), Response::ExecutionResult(None));
+			// Another contract can neither read nor consume the response.
+			assert!(XcmCallbacks::take_response(&BOB, query_id).is_none());
+			assert!(matches!(
+				XcmCallbacks::take_response(&ALICE, query_id),
+				Some(QueryResponseStatus::Ready { response: Response::ExecutionResult(None), .. })
+			));
+
+			// A response is only taken once.
+			assert!(XcmCallbacks::take_response(&ALICE, query_id).is_none());
+			assert!(!QueryOwners::<Test>::contains_key(query_id));
+		});
+	}
+
+	#[test]
+	fn callback_queries_cannot_be_taken() {
+		new_test_ext().execute_with(|| {
+			let query_id = XcmCallbacks::new_query(
+				ALICE,
+				querier(&ALICE),
+				sibling(),
+				10,
+				[0; 4],
+				Weight::zero(),
+			)
+			.unwrap();
+			assert!(XcmCallbacks::take_response(&ALICE, query_id).is_none());
+		});
+	}
+
+	#[test]
+	fn gas_limit_is_capped() {
+		new_test_ext().execute_with(|| {
+			let gas_limit = MaxCallbackGas::get().add_ref_time(1);
+			assert_noop!(
+				XcmCallbacks::new_query(ALICE, querier(&ALICE), sibling(), 10, [0; 4], gas_limit),
+				Error::<Test>::GasLimitTooHigh
+			);
+		});
+	}
+
+	#[test]
+	fn only_responses_are_accepted() {
+		new_test_ext().execute_with(|| {
+			let query_id = new_callback_query(&ALICE);
+			let response = Response::ExecutionResult(None);
+			assert_noop!(
+				XcmCallbacks::on_response(RuntimeOrigin::signed(ALICE), query_id, response.clone()),
+				DispatchError::BadOrigin
+			);
+			assert_noop!(
+				XcmCallbacks::on_response(RuntimeOrigin::root(), query_id, response),
+				DispatchError::BadOrigin
+			);
+			assert!(Callbacks::<Test>::contains_key(query_id));
+		});
+	}
+
+	#[test]
+	fn unknown_query_is_ignored() {
+		new_test_ext().execute_with(|| {
+			let info =
+				XcmCallbacks::on_response(response_origin(), 42, Response::ExecutionResult(None))
+					.unwrap();
+			let base = <Test as frame_system::Config>::DbWeight::get().reads_writes(1, 1);
+			assert_eq!(info.actual_weight, Some(base));
+			assert!(System::events().is_empty());
+		});
+	}
+
+	#[test]
+	fn callback_gets_the_outcome() {
+		new_test_ext().execute_with(|| {
+			let contract = instantiate_callback_contract();
+			let succeeded = new_callback_query(&contract);
+			let failed = new_callback_query(&contract);
+			System::reset_events();
+
+			// Delivered by `pallet-xcm`, like a response arriving from the sibling.
+			respond(succeeded, &querier(&contract), Response::ExecutionResult(None));
+			let response = Response::ExecutionResult(Some((0, XcmError::Unimplemented)));
+			XcmCallbacks::on_response(response_origin(), failed, response).unwrap();
+
+			let input = |query_id: QueryId, status: u8| {
+				let mut input = vec![1, 2, 3, 4];
+				(query_id, status).encode_to(&mut input);
+				input
+			};
+			assert_eq!(emitted(&contract), vec![input(succeeded, 1), input(failed, 2)]);
+			System::assert_has_event(
+				Event::<Test>::CallbackCalled {
+					query_id: succeeded,
+					contract: contract.clone(),
+					success: true,
+				}
+				.into(),
+			);
+			System::assert_has_event(
+				Event::<Test>::CallbackCalled { query_id: failed, contract, success: true }.into(),
+			);
+			assert!(!Callbacks::<Test>::contains_key(succeeded));
+			assert!(!Callbacks::<Test>::contains_key(failed));
+		});
+	}
+
+	#[test]
+	fn unused_weight_is_refunded() {
+		new_test_ext().execute_with(|| {
+			let contract = instantiate_callback_contract();
+			let free = Balances::free_balance(&contract);
+			let query_id = new_callback_query(&contract);
+			let deposit = TestWeightPrice::convert(CALLBACK_GAS);
+			assert_eq!(held(&contract), deposit);
+			assert_eq!(Balances::free_balance(&contract), free - deposit);
+			assert_eq!(
+				XcmCallbacks::response_weight(query_id),
+				XcmCallbacks::response_weight_limit()
+			);
+
+			let info = XcmCallbacks::on_response(
+				response_origin(),
+				query_id,
+				Response::ExecutionResult(None),
+			)
+			.unwrap();
+			let actual = info.actual_weight.unwrap();
+			assert!(actual.all_lt(XcmCallbacks::response_weight_limit()));
+			// The callback ran and its gas is accounted for.
+			let call = <Test as pallet_contracts::Config>::WeightInfo::call();
+			assert!(actual.any_gt(call));
+			assert_eq!(XcmCallbacks::response_weight(query_id), Weight::zero());
+
+			// The gas used is paid for, the rest of the deposit is released.
+			assert_eq!(held(&contract), 0);
+			let paid = free - Balances::free_balance(&contract);
+			assert!(paid > 0 && paid < deposit);
+		});
+	}
+
+	#[test]
+	fn contract_pays_for_the_gas_limit() {
+		new_test_ext().execute_with(|| {
+			assert_noop!(
+				XcmCallbacks::new_query(BOB, querier(&BOB), sibling(), 10, [0; 4], CALLBACK_GAS),
+				Error::<Test>::InsufficientBalance
+			);
+		});
+	}
+
+	#[test]
+	fn expired_queries_are_removed() {
+		new_test_ext().execute_with(|| {
+			let contract = instantiate_callback_contract();
+			let free = Balances::free_balance(&contract);
+			let callback = new_callback_query(&contract);
+			let polled =
+				XcmCallbacks::new_polled_query(contract.clone(), querier(&contract), sibling(), 10);
+
+			for query_id in [callback, polled] {
+				assert_noop!(
+					XcmCallbacks::remove_expired(RuntimeOrigin::signed(BOB), query_id),
+					Error::<Test>::NotExpired
+				);
+			}
+			System::set_block_number(11);
+			for query_id in [callback, polled] {
+				assert_ok!(XcmCallbacks::remove_expired(RuntimeOrigin::signed(BOB), query_id));
+				System::assert_last_event(
+					Event::<Test>::QueryExpired { query_id, contract: contract.clone() }.into(),
+				);
+				assert_noop!(
+					XcmCallbacks::remove_expired(RuntimeOrigin::signed(BOB), query_id),
+					Error::<Test>::UnknownQuery
+				);
+			}
+			assert!(!Callbacks::<Test>::contains_key(callback));
+			assert!(!QueryOwners::<Test>::contains_key(polled));
+			// All of the deposit is released.
+			assert_eq!(held(&contract), 0);
+			assert_eq!(Balances::free_balance(&contract), free);
+
+			// A late response is ignored.
+			System::reset_events();
+			respond(callback, &querier(&contract), Response::ExecutionResult(None));
+			assert!(emitted(&contract).is_empty());
+			assert!(XcmCallbacks::take_response(&contract, polled).is_none());
+		});
+	}
+}
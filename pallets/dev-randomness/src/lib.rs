@@ -0,0 +1,191 @@
+//! # Dev Randomness Pallet
+//!
+//! A randomness source for development chains that can be made deterministic. While no seed is
+//! set, the runtime's regular source is used. Once a seed is set (at genesis, e.g. with the
+//! `--random-seed` flag of the dev node, or later by the admin origin), the randomness only
+//! depends on the seed, the subject and the block number, so contract tests that use randomness
+//! give the same results on every run.
+//!
+//! This is **not** a secure source of randomness, not even without a seed.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+pub use pallet::*;
+
+#[frame_support::pallet]
+pub mod pallet {
+	use frame_support::{pallet_prelude::*, traits::Randomness};
+	use frame_system::pallet_prelude::*;
+	use sp_runtime::traits::Hash;
+
+	#[pallet::pallet]
+	pub struct Pallet<T>(_);
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		/// The overarching event type.
+		type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
+
+		/// The origin which may set the seed.
+		type AdminOrigin: EnsureOrigin<Self::RuntimeOrigin>;
+
+		/// The randomness source used while no seed is set.
+		type Fallback: Randomness<Self::Hash, BlockNumberFor<Self>>;
+	}
+
+	/// The seed of the randomness, the fallback source is used if there is none.
+	#[pallet::storage]
+	pub type Seed<T: Config> = StorageValue<_, [u8; 32], OptionQuery>;
+
+	#[pallet::genesis_config]
+	#[derive(frame_support::DefaultNoBound)]
+	pub struct GenesisConfig<T: Config> {
+		/// The seed from genesis on.
+		pub seed: Option<[u8; 32]>,
+		#[serde(skip)]
+		pub _config: PhantomData<T>,
+	}
+
+	#[pallet::genesis_build]
+	impl<T: Config> BuildGenesisConfig for GenesisConfig<T> {
+		fn build(&self) {
+			if let Some(seed) = self.seed {
+				Seed::<T>::put(seed);
+			}
+		}
+	}
+
+	#[pallet::event]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// The seed was set or, if `None`, removed.
+		SeedSet { seed: Option<[u8; 32]> },
+	}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Set the seed of the randomness, or remove it to use the fallback source again.
+		#[pallet::call_index(0)]
+		#[pallet::weight(T::DbWeight::get().writes(1))]
+		pub fn set_seed(origin: OriginFor<T>, seed: Option<[u8; 32]>) -> DispatchResult {
+			T::AdminOrigin::ensure_origin(origin)?;
+			Seed::<T>::set(seed);
+			Self::deposit_event(Event::SeedSet { seed });
+			Ok(())
+		}
+	}
+
+	impl<T: Config> Randomness<T::Hash, BlockNumberFor<T>> for Pallet<T> {
+		fn random(subject: &[u8]) -> (T::Hash, BlockNumberFor<T>) {
+			let Some(seed) = Seed::<T>::get() else { return T::Fallback::random(subject) };
+			let block_number = frame_system::Pallet::<T>::block_number();
+			(T::Hashing::hash_of(&(seed, subject, block_number)), block_number)
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use frame_support::{
+		assert_noop, assert_ok, construct_runtime, derive_impl, traits::Randomness,
+	};
+	use frame_system::EnsureRoot;
+	use sp_runtime::{testing::H256, BuildStorage, DispatchError};
+
+	type Block = frame_system::mocking::MockBlock<Test>;
+
+	construct_runtime!(
+		pub enum Test {
+			System: frame_system,
+			DevRandomness: crate,
+		}
+	);
+
+	#[derive_impl(frame_system::config_preludes::TestDefaultConfig as frame_system::DefaultConfig)]
+	impl frame_system::Config for Test {
+		type Block = Block;
+	}
+
+	/// Always returns [`FALLBACK`].
+	pub struct TestFallback;
+
+	impl Randomness<H256, u64> for TestFallback {
+		fn random(_subject: &[u8]) -> (H256, u64) {
+			FALLBACK
+		}
+	}
+
+	impl Config for Test {
+		type RuntimeEvent = RuntimeEvent;
+		type AdminOrigin = EnsureRoot<u64>;
+		type Fallback = TestFallback;
+	}
+
+	const FALLBACK: (H256, u64) = (H256::repeat_byte(9), 0);
+	const SEED: [u8; 32] = [1; 32];
+
+	fn new_test_ext(seed: Option<[u8; 32]>) -> sp_io::TestExternalities {
+		let storage = RuntimeGenesisConfig {
+			system: Default::default(),
+			dev_randomness: GenesisConfig { seed, ..Default::default() },
+		}
+		.build_storage()
+		.unwrap();
+		let mut ext = sp_io::TestExternalities::from(storage);
+		ext.execute_with(|| System::set_block_number(1));
+		ext
+	}
+
+	#[test]
+	fn falls_back_without_seed() {
+		new_test_ext(None).execute_with(|| {
+			assert_eq!(DevRandomness::random(b"subject"), FALLBACK);
+		});
+	}
+
+	#[test]
+	fn seeded_output_is_reproducible() {
+		let random = |subject: &[u8]| {
+			new_test_ext(Some(SEED)).execute_with(|| DevRandomness::random(subject))
+		};
+		let (output, block_number) = random(b"subject");
+		assert_eq!(block_number, 1);
+		assert_ne!(output, FALLBACK.0);
+		// The same on every chain started with the seed.
+		assert_eq!(random(b"subject"), (output, block_number));
+		assert_ne!(random(b"other subject").0, output);
+
+		new_test_ext(Some([2; 32])).execute_with(|| {
+			assert_ne!(DevRandomness::random(b"subject").0, output);
+		});
+		new_test_ext(Some(SEED)).execute_with(|| {
+			System::set_block_number(2);
+			assert_ne!(DevRandomness::random(b"subject"), (output, block_number));
+		});
+	}
+
+	#[test]
+	fn set_seed_switches_the_source() {
+		let expected = new_test_ext(Some(SEED)).execute_with(|| DevRandomness::random(b"subject"));
+		new_test_ext(None).execute_with(|| {
+			assert_ok!(DevRandomness::set_seed(RuntimeOrigin::root(), Some(SEED)));
+			System::assert_last_event(Event::<Test>::SeedSet { seed: Some(SEED) }.into());
+			assert_eq!(DevRandomness::random(b"subject"), expected);
+
+			assert_ok!(DevRandomness::set_seed(RuntimeOrigin::root(), None));
+			System::assert_last_event(Event::<Test>::SeedSet { seed: None }.into());
+			assert_eq!(DevRandomness::random(b"subject"), FALLBACK);
+		});
+	}
+
+	#[test]
+	fn only_admin_sets_seed() {
+		new_test_ext(None).execute_with(|| {
+			assert_noop!(
+				DevRandomness::set_seed(RuntimeOrigin::signed(1), Some(SEED)),
+				DispatchError::BadOrigin
+			);
+		});
+	}
+}
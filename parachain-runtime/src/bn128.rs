@@ -1 +0,0 @@
-../../runtime/src/bn128.rs
\ No newline at end of file
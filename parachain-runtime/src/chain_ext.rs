@@ -1 +0,0 @@
-../../runtime/src/chain_ext.rs
\ No newline at end of file
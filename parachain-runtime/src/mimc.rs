@@ -1 +0,0 @@
-../../runtime/src/mimc.rs
\ No newline at end of file
@@ -1,19 +1,13 @@
 use crate::{
-	Balance, Balances, BalancesCall, Perbill, RandomnessCollectiveFlip, Runtime, RuntimeCall,
-	RuntimeEvent, RuntimeHoldReason, Timestamp,
+	AccountId, Balance, Balances, ContractsCallFilter, ContractsLimits, DevRandomness, Perbill,
+	RandomnessCollectiveFlip, Runtime, RuntimeCall, RuntimeEvent, RuntimeHoldReason, Timestamp,
 };
 use frame_support::{
 	parameter_types,
 	traits::{ConstBool, ConstU32},
 };
-
-pub enum AllowBalancesCall {}
-
-impl frame_support::traits::Contains<RuntimeCall> for AllowBalancesCall {
-	fn contains(call: &RuntimeCall) -> bool {
-		matches!(call, RuntimeCall::Balances(BalancesCall::transfer_allow_death { .. }))
-	}
-}
+use frame_system::EnsureRoot;
+use sp_core::{hashing::keccak_256, H160};
 
 // Unit = the base number of indivisible units for balances
 const UNIT: Balance = 1_000_000_000_000;
@@ -23,10 +17,17 @@ const fn deposit(items: u32, bytes: u32) -> Balance {
 	(items as Balance * UNIT + (bytes as Balance) * (5 * MILLIUNIT / 100)) / 10
 }
 
+/// The length of the `CallStack` of `pallet-contracts`.
+#[cfg(not(feature = "strict"))]
+const CALL_STACK_DEPTH: usize = 23;
+/// The length of the `CallStack` of `pallet-contracts` on production chains.
+#[cfg(feature = "strict")]
+const CALL_STACK_DEPTH: usize = 5;
+
 fn schedule<T: pallet_contracts::Config>() -> pallet_contracts::Schedule<T> {
 	pallet_contracts::Schedule {
 		limits: pallet_contracts::Limits {
-			runtime_memory: 1024 * 1024 * 1024,
+			runtime_memory: ContractsLimits::limits().runtime_memory,
 			..Default::default()
 		},
 		..Default::default()
@@ -34,17 +35,89 @@ fn schedule<T: pallet_contracts::Config>() -> pallet_contracts::Schedule<T> {
 }
 
 parameter_types! {
-	pub const DepositPerItem: Balance = deposit(1, 0);
-	pub const DepositPerByte: Balance = deposit(0, 1);
 	pub Schedule: pallet_contracts::Schedule<Runtime> = schedule::<Runtime>();
-	pub const DefaultDepositLimit: Balance = deposit(1024, 1024 * 1024);
 	pub const CodeHashLockupDepositPercent: Perbill = Perbill::from_percent(0);
 	pub const MaxDelegateDependencies: u32 = 32;
+	pub MaxMemorySize: u32 = pallet_contracts::Limits::default().max_memory_size();
+	/// The limits of `pallet-contracts` unless the chain spec or sudo set others.
+	pub DefaultLimits: pallet_contracts_limits::Limits<Balance> = pallet_contracts_limits::Limits {
+		// This node is geared towards development and testing of contracts.
+		// We decided to increase the default allowed contract size for this
+		// reason (the default is `128 * 1024`).
+		//
+		// Our reasoning is that the error code `CodeTooLarge` is thrown
+		// if a too-large contract is uploaded. We noticed that it poses
+		// less friction during development when the requirement here is
+		// just more lax.
+		//
+		// With the `strict` feature, the limits of production chains apply.
+		max_code_len: if cfg!(feature = "strict") { 128 * 1024 } else { 256 * 1024 },
+		runtime_memory: if cfg!(feature = "strict") {
+			128 * 1024 * 1024
+		} else {
+			1024 * 1024 * 1024
+		},
+		max_debug_buffer_len: 2 * 1024 * 1024,
+		deposit_per_item: deposit(1, 0),
+		deposit_per_byte: deposit(0, 1),
+		default_deposit_limit: deposit(1024, 1024 * 1024),
+	};
+	/// With the `strict` feature, sudo may not relax the limits of production chains.
+	pub StrictLimits: Option<pallet_contracts_limits::Limits<Balance>> =
+		cfg!(feature = "strict").then(DefaultLimits::get);
+}
+
+/// The chain extension shared by both runtimes.
+#[cfg(not(any(feature = "strict", all(feature = "parachain", feature = "ext-xcm"))))]
+pub type RuntimeChainExtension = contracts_chain_extension::Extension<Runtime>;
+/// The chain extension shared by both runtimes, with the XCM functions on the parachain.
+#[cfg(all(not(feature = "strict"), feature = "parachain", feature = "ext-xcm"))]
+pub type RuntimeChainExtension =
+	contracts_chain_extension::Extension<Runtime, contracts_chain_extension::PalletXcm<Runtime>>;
+/// No chain extension with the `strict` feature, whatever `ext-*` features are enabled, as
+/// production chains do not have the one of this node.
+#[cfg(feature = "strict")]
+pub type RuntimeChainExtension = ();
+
+/// Derives the addresses of contracts like the `CREATE2` opcode of Ethereum does:
+/// `keccak256(0xff ++ deployer ++ salt ++ code_hash)[12..]`, with the deployer as the address
+/// `pallet_account_mapping::derived_address` derives from its account. The address an account
+/// registered in `pallet-account-mapping` is not used, so mapping or unmapping one does not change
+/// the addresses of the contracts the account deploys. A contract deploying another one is
+/// identified by its own address, as its account is a padded address.
+///
+/// Salts of 32 bytes are used as they are, like on Ethereum. Any other salt is hashed with
+/// keccak256 to 32 bytes first, so it gives the same address as a 32 byte salt that is its hash.
+/// Unlike the init code on Ethereum, the constructor input is not part of the address. The 20 byte
+/// address is padded with `0xee` bytes to an `AccountId`, which `pallet-account-mapping` maps back
+/// to the address.
+pub struct Create2AddressGenerator;
+
+impl pallet_contracts::AddressGenerator<Runtime> for Create2AddressGenerator {
+	fn contract_address(
+		deploying_address: &AccountId,
+		code_hash: &crate::Hash,
+		_input_data: &[u8],
+		salt: &[u8],
+	) -> AccountId {
+		let salt = match <[u8; 32]>::try_from(salt) {
+			Ok(salt) => salt,
+			Err(_) => keccak_256(salt),
+		};
+		let mut preimage = [0u8; 85];
+		preimage[0] = 0xff;
+		preimage[1..21]
+			.copy_from_slice(pallet_account_mapping::derived_address(deploying_address).as_bytes());
+		preimage[21..53].copy_from_slice(&salt);
+		preimage[53..].copy_from_slice(code_hash.as_ref());
+
+		pallet_account_mapping::padded_account(&H160::from_slice(&keccak_256(&preimage)[12..]))
+	}
 }
 
 impl pallet_contracts::Config for Runtime {
 	type Time = Timestamp;
-	type Randomness = RandomnessCollectiveFlip;
+	type Randomness = DevRandomness;
 	type Currency = Balances;
 	type RuntimeEvent = RuntimeEvent;
 	type RuntimeCall = RuntimeCall;
@@ -55,33 +128,42 @@ impl pallet_contracts::Config for Runtime {
 	/// and make sure they are stable. Dispatchables exposed to contracts are not allowed to
 	/// change because that would break already deployed contracts. The `RuntimeCall` structure
 	/// itself is not allowed to change the indices of existing pallets, too.
-	type CallFilter = AllowBalancesCall;
-	type DepositPerItem = DepositPerItem;
-	type DepositPerByte = DepositPerByte;
-	type CallStack = [pallet_contracts::Frame<Self>; 23];
+	///
+	/// The allowed calls are kept in the storage of `pallet-contracts-call-filter`, so they can be
+	/// changed by sudo while testing. The dev chain specs only allow
+	/// `Balances::transfer_allow_death`.
+	type CallFilter = ContractsCallFilter;
+	type DepositPerItem = pallet_contracts_limits::DepositPerItem<Self>;
+	type DepositPerByte = pallet_contracts_limits::DepositPerByte<Self>;
+	type CallStack = [pallet_contracts::Frame<Self>; CALL_STACK_DEPTH];
 	type WeightPrice = pallet_transaction_payment::Pallet<Self>;
 	type WeightInfo = pallet_contracts::weights::SubstrateWeight<Self>;
-	type ChainExtension = crate::chain_ext::FetchRandomExtension;
+	type ChainExtension = RuntimeChainExtension;
+	/// `Schedule`, with the costs `ContractsDevApi::profile_call` measures set to zero while it
+	/// does so.
+	#[cfg(not(feature = "strict"))]
+	type Schedule = contracts_dev_api::ProfiledSchedule<Schedule>;
+	#[cfg(feature = "strict")]
 	type Schedule = Schedule;
+	#[cfg(feature = "eth-addresses")]
+	type AddressGenerator = Create2AddressGenerator;
+	#[cfg(not(feature = "eth-addresses"))]
 	type AddressGenerator = pallet_contracts::DefaultAddressGenerator;
-	// This node is geared towards development and testing of contracts.
-	// We decided to increase the default allowed contract size for this
-	// reason (the default is `128 * 1024`).
-	//
-	// Our reasoning is that the error code `CodeTooLarge` is thrown
-	// if a too-large contract is uploaded. We noticed that it poses
-	// less friction during development when the requirement here is
-	// just more lax.
-	type MaxCodeLen = ConstU32<{ 256 * 1024 }>;
-	type DefaultDepositLimit = DefaultDepositLimit;
+	type MaxCodeLen = pallet_contracts_limits::MaxCodeLen<Self>;
+	type DefaultDepositLimit = pallet_contracts_limits::DefaultDepositLimit<Self>;
 	type MaxStorageKeyLen = ConstU32<128>;
-	type MaxDebugBufferLen = ConstU32<{ 2 * 1024 * 1024 }>;
-	type UnsafeUnstableInterface = ConstBool<true>;
+	type MaxDebugBufferLen = pallet_contracts_limits::MaxDebugBufferLen<Self>;
+	type UnsafeUnstableInterface = ConstBool<{ !cfg!(feature = "strict") }>;
 	type CodeHashLockupDepositPercent = CodeHashLockupDepositPercent;
 	type MaxDelegateDependencies = MaxDelegateDependencies;
 	type RuntimeHoldReason = RuntimeHoldReason;
 
 	type Environment = ();
+	/// Records and replays the calls of `ContractsDevApi::trace_call`, a no-op for any other
+	/// execution.
+	#[cfg(not(feature = "strict"))]
+	type Debug = contracts_dev_api::CallTracer;
+	#[cfg(feature = "strict")]
 	type Debug = ();
 	type ApiVersion = ();
 	type Migrations = ();
@@ -90,3 +172,142 @@ impl pallet_contracts::Config for Runtime {
 	#[cfg(not(feature = "parachain"))]
 	type Xcm = ();
 }
+
+impl pallet_dev_randomness::Config for Runtime {
+	type RuntimeEvent = RuntimeEvent;
+	type AdminOrigin = EnsureRoot<AccountId>;
+	type Fallback = RandomnessCollectiveFlip;
+}
+
+impl pallet_contracts_call_filter::Config for Runtime {
+	type RuntimeEvent = RuntimeEvent;
+	type AdminOrigin = EnsureRoot<AccountId>;
+}
+
+impl pallet_chain_extension_allowlist::Config for Runtime {
+	type RuntimeEvent = RuntimeEvent;
+	type AdminOrigin = EnsureRoot<AccountId>;
+}
+
+impl pallet_contracts_limits::Config for Runtime {
+	type RuntimeEvent = RuntimeEvent;
+	type AdminOrigin = EnsureRoot<AccountId>;
+	type Balance = Balance;
+	type DefaultLimits = DefaultLimits;
+	type CallStackDepth = ConstU32<{ CALL_STACK_DEPTH as u32 }>;
+	type MaxMemorySize = MaxMemorySize;
+	type StrictLimits = StrictLimits;
+}
+
+impl pallet_account_mapping::Config for Runtime {
+	type RuntimeEvent = RuntimeEvent;
+}
+
+/// The parachain id as little-endian word, used as chain id by contracts.
+#[cfg(feature = "parachain")]
+pub struct ParachainChainId;
+
+#[cfg(feature = "parachain")]
+impl frame_support::traits::Get<[u8; 32]> for ParachainChainId {
+	fn get() -> [u8; 32] {
+		let mut id = [0u8; 32];
+		id[..4].copy_from_slice(&u32::from(crate::ParachainInfo::parachain_id()).to_le_bytes());
+		id
+	}
+}
+
+/// The relay chain state from the validation data of `cumulus-pallet-parachain-system`.
+#[cfg(feature = "parachain")]
+pub struct RelayChainState;
+
+#[cfg(feature = "parachain")]
+impl pallet_contracts_context::RelayChainContext for RelayChainState {
+	fn relay_parent_number() -> Option<u32> {
+		crate::ParachainSystem::validation_data().map(|data| data.relay_parent_number)
+	}
+
+	fn relay_parent_storage_root() -> Option<[u8; 32]> {
+		crate::ParachainSystem::validation_data().map(|data| data.relay_parent_storage_root.into())
+	}
+
+	fn para_id() -> Option<u32> {
+		Some(crate::ParachainInfo::parachain_id().into())
+	}
+}
+
+impl pallet_contracts_context::Config for Runtime {
+	#[cfg(feature = "parachain")]
+	type ChainId = ParachainChainId;
+	#[cfg(not(feature = "parachain"))]
+	type ChainId = pallet_contracts_context::GenesisHash<Self>;
+	#[cfg(feature = "parachain")]
+	type RelayChain = RelayChainState;
+	#[cfg(not(feature = "parachain"))]
+	type RelayChain = ();
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use frame_support::assert_ok;
+	use hex_literal::hex;
+	use pallet_contracts::AddressGenerator;
+	use sp_runtime::BuildStorage;
+
+	fn create2(deployer: [u8; 20], salt: &[u8], code_hash: [u8; 32]) -> H160 {
+		let deployer = pallet_account_mapping::padded_account(&H160(deployer));
+		let account =
+			Create2AddressGenerator::contract_address(&deployer, &code_hash.into(), &[], salt);
+		pallet_account_mapping::derived_address(&account)
+	}
+
+	#[test]
+	fn create2_matches_eip1014_vectors() {
+		// Example 0: the init code is `0x00`.
+		assert_eq!(
+			create2([0; 20], &[0; 32], keccak_256(&[0])),
+			H160(hex!("4d1a2e2bb4f88f0250f26ffff098b0b30b26bf38")),
+		);
+		// Example 5: the init code is `0xdeadbeef`.
+		assert_eq!(
+			create2(
+				hex!("00000000000000000000000000000000deadbeef"),
+				&hex!("00000000000000000000000000000000000000000000000000000000cafebabe"),
+				keccak_256(&hex!("deadbeef")),
+			),
+			H160(hex!("60f3f640a8508fc6a86d45df051962668e1e8ac7")),
+		);
+	}
+
+	#[test]
+	fn create2_addresses_are_deterministic() {
+		let deployer = hex!("00000000000000000000000000000000deadbeef");
+		let address = create2(deployer, b"salt", [1; 32]);
+		assert_eq!(create2(deployer, b"salt", [1; 32]), address);
+		// Other salts are hashed to 32 bytes.
+		assert_eq!(create2(deployer, &keccak_256(b"salt"), [1; 32]), address);
+		assert_ne!(create2(deployer, b"other salt", [1; 32]), address);
+		assert_ne!(create2(deployer, b"salt", [2; 32]), address);
+		assert_ne!(create2([0; 20], b"salt", [1; 32]), address);
+	}
+
+	#[test]
+	fn strict_limits_are_not_relaxed() {
+		let storage = crate::RuntimeGenesisConfig::default().build_storage().unwrap();
+		sp_io::TestExternalities::new(storage).execute_with(|| {
+			let laxer = pallet_contracts_limits::Limits {
+				max_debug_buffer_len: 4 * 1024 * 1024,
+				..DefaultLimits::get()
+			};
+			let result = crate::ContractsLimits::set_limits(crate::RuntimeOrigin::root(), laxer);
+			if cfg!(feature = "strict") {
+				assert_eq!(
+					result,
+					Err(pallet_contracts_limits::Error::<Runtime>::LaxerThanStrictLimits.into())
+				);
+			} else {
+				assert_ok!(result);
+			}
+		});
+	}
+}
@@ -9,9 +9,7 @@ include!(concat!(env!("OUT_DIR"), "/wasm_binary.rs"));
 mod assets_config;
 mod contracts_config;
 
-mod bn128;
-mod chain_ext;
-mod mimc;
+pub use contracts_chain_extension::{ChainExtensionApi, ExtensionGroup};
 
 use frame_support::{
 	derive_impl,
@@ -127,9 +125,16 @@ const NORMAL_DISPATCH_RATIO: Perbill = Perbill::from_percent(75);
 const AVERAGE_ON_INITIALIZE_RATIO: Perbill = Perbill::from_percent(10);
 
 /// We allow for 2 seconds of compute with a 6 second average block time, with maximum proof size.
+#[cfg(not(feature = "strict"))]
 const MAXIMUM_BLOCK_WEIGHT: Weight =
 	Weight::from_parts(WEIGHT_REF_TIME_PER_SECOND.saturating_mul(2), u64::MAX);
 
+/// The maximum weight of a parachain block: 0.5 of a second of compute and the 5 MiB proof size
+/// limit of the relay chain.
+#[cfg(feature = "strict")]
+const MAXIMUM_BLOCK_WEIGHT: Weight =
+	Weight::from_parts(WEIGHT_REF_TIME_PER_SECOND.saturating_div(2), 5 * 1024 * 1024);
+
 // Prints debug output of the `contracts` pallet to stdout if the node is
 // started with `-lruntime::contracts=debug`.
 const CONTRACTS_DEBUG_OUTPUT: pallet_contracts::DebugInfo =
@@ -209,7 +214,8 @@ parameter_types! {
 }
 
 impl pallet_authorship::Config for Runtime {
-	type FindAuthor = ();
+	// There is no block author with instant seal, so the dev node reports a configured one.
+	type FindAuthor = pallet_contracts_context::DevAuthor<Self>;
 	type EventHandler = ();
 }
 
@@ -283,6 +289,12 @@ construct_runtime!(
 		Sudo: pallet_sudo,
 		Contracts: pallet_contracts,
 		Assets: pallet_assets,
+		ChainExtensionAllowlist: pallet_chain_extension_allowlist,
+		ContractsContext: pallet_contracts_context,
+		DevRandomness: pallet_dev_randomness,
+		ContractsCallFilter: pallet_contracts_call_filter,
+		AccountMapping: pallet_account_mapping,
+		ContractsLimits: pallet_contracts_limits,
 	}
 );
 
@@ -301,7 +313,8 @@ pub type SignedExtra = (
 	frame_system::CheckEra<Runtime>,
 	frame_system::CheckNonce<Runtime>,
 	frame_system::CheckWeight<Runtime>,
-	pallet_transaction_payment::ChargeTransactionPayment<Runtime>,
+	pallet_contracts_context::ChargeTransactionPayment<Runtime>,
+	pallet_contracts_context::RecordTxOrigin<Runtime>,
 );
 /// The payload being signed in transactions.
 pub type SignedPayload = generic::SignedPayload<RuntimeCall, SignedExtra>;
@@ -516,6 +529,184 @@ impl_runtime_apis! {
 	}
 
 
+	#[api_version(5)]
+	impl contracts_dev_api::ContractsDevApi<Block, AccountId, Balance, BlockNumber, Hash>
+		for Runtime
+	{
+		fn trace_call(
+			origin: AccountId,
+			dest: AccountId,
+			value: Balance,
+			gas_limit: Option<Weight>,
+			storage_deposit_limit: Option<Balance>,
+			input_data: Vec<u8>,
+		) -> contracts_dev_api::TracedCall<AccountId, Balance> {
+			let gas_limit = gas_limit.unwrap_or(RuntimeBlockWeights::get().max_block);
+			contracts_dev_api::trace_call::<Runtime>(
+				origin,
+				dest,
+				value,
+				gas_limit,
+				storage_deposit_limit,
+				input_data,
+			)
+		}
+
+		fn profile_call(
+			origin: AccountId,
+			dest: AccountId,
+			value: Balance,
+			gas_limit: Option<Weight>,
+			storage_deposit_limit: Option<Balance>,
+			input_data: Vec<u8>,
+		) -> contracts_dev_api::CallProfile {
+			let gas_limit = gas_limit.unwrap_or(RuntimeBlockWeights::get().max_block);
+			contracts_dev_api::profile_call::<Runtime>(
+				origin,
+				dest,
+				value,
+				gas_limit,
+				storage_deposit_limit,
+				input_data,
+			)
+		}
+
+		fn call_with_overrides(
+			origin: AccountId,
+			dest: AccountId,
+			value: Balance,
+			gas_limit: Option<Weight>,
+			storage_deposit_limit: Option<Balance>,
+			input_data: Vec<u8>,
+			overrides: contracts_dev_api::StateOverrides<AccountId, Balance, Hash, BlockNumber>,
+		) -> pallet_contracts::ContractExecResult<Balance, ()> {
+			let gas_limit = gas_limit.unwrap_or(RuntimeBlockWeights::get().max_block);
+			contracts_dev_api::call_with_overrides::<Runtime>(
+				origin,
+				dest,
+				value,
+				gas_limit,
+				storage_deposit_limit,
+				input_data,
+				overrides,
+				CONTRACTS_DEBUG_OUTPUT,
+			)
+		}
+
+		fn instantiate_with_overrides(
+			origin: AccountId,
+			value: Balance,
+			gas_limit: Option<Weight>,
+			storage_deposit_limit: Option<Balance>,
+			code: pallet_contracts::Code<Hash>,
+			data: Vec<u8>,
+			salt: Vec<u8>,
+			overrides: contracts_dev_api::StateOverrides<AccountId, Balance, Hash, BlockNumber>,
+		) -> pallet_contracts::ContractInstantiateResult<AccountId, Balance, ()> {
+			let gas_limit = gas_limit.unwrap_or(RuntimeBlockWeights::get().max_block);
+			contracts_dev_api::instantiate_with_overrides::<Runtime>(
+				origin,
+				value,
+				gas_limit,
+				storage_deposit_limit,
+				code,
+				data,
+				salt,
+				overrides,
+				CONTRACTS_DEBUG_OUTPUT,
+			)
+		}
+
+		fn estimate_call(
+			origin: AccountId,
+			dest: AccountId,
+			value: Balance,
+			gas_limit: Option<Weight>,
+			storage_deposit_limit: Option<Balance>,
+			input_data: Vec<u8>,
+		) -> contracts_dev_api::GasEstimate<Balance> {
+			let gas_limit = gas_limit.unwrap_or(RuntimeBlockWeights::get().max_block);
+			contracts_dev_api::estimate_call::<Runtime>(
+				origin,
+				dest,
+				value,
+				gas_limit,
+				storage_deposit_limit,
+				input_data,
+			)
+		}
+
+		fn estimate_instantiate(
+			origin: AccountId,
+			value: Balance,
+			gas_limit: Option<Weight>,
+			storage_deposit_limit: Option<Balance>,
+			code: pallet_contracts::Code<Hash>,
+			data: Vec<u8>,
+			salt: Vec<u8>,
+		) -> contracts_dev_api::GasEstimate<Balance> {
+			let gas_limit = gas_limit.unwrap_or(RuntimeBlockWeights::get().max_block);
+			contracts_dev_api::estimate_instantiate::<Runtime>(
+				origin,
+				value,
+				gas_limit,
+				storage_deposit_limit,
+				code,
+				data,
+				salt,
+			)
+		}
+
+		fn call_with_determinism(
+			origin: AccountId,
+			dest: AccountId,
+			value: Balance,
+			gas_limit: Option<Weight>,
+			storage_deposit_limit: Option<Balance>,
+			input_data: Vec<u8>,
+			determinism: pallet_contracts::Determinism,
+		) -> pallet_contracts::ContractExecResult<Balance, ()> {
+			let gas_limit = gas_limit.unwrap_or(RuntimeBlockWeights::get().max_block);
+			contracts_dev_api::call_with_determinism::<Runtime>(
+				origin,
+				dest,
+				value,
+				gas_limit,
+				storage_deposit_limit,
+				input_data,
+				determinism,
+				CONTRACTS_DEBUG_OUTPUT,
+			)
+		}
+	}
+
+	impl pallet_contracts_call_filter::ContractsCallFilterApi<Block> for Runtime {
+		fn allowed_calls() -> Vec<(Vec<u8>, Vec<u8>)> {
+			ContractsCallFilter::allowed_calls()
+		}
+	}
+
+	impl pallet_account_mapping::AccountMappingApi<Block> for Runtime {
+		fn address_of(account: AccountId) -> sp_core::H160 {
+			AccountMapping::address_of(&account)
+		}
+
+		fn account_of(address: sp_core::H160) -> AccountId {
+			AccountMapping::account_of(&address)
+		}
+	}
+
+	impl contracts_chain_extension::ChainExtensionApi<Block> for Runtime {
+		fn active_groups() -> Vec<ExtensionGroup> {
+			// The `strict` runtime has no chain extension.
+			if cfg!(feature = "strict") {
+				Vec::new()
+			} else {
+				contracts_chain_extension::active_groups()
+			}
+		}
+	}
+
 	impl sp_genesis_builder::GenesisBuilder<Block> for Runtime {
 		fn create_default_config() -> Vec<u8> {
 			create_default_config::<RuntimeGenesisConfig>()